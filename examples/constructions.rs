@@ -0,0 +1,51 @@
+//! 比较三种DFA构造方法的状态数与构造时间:
+//! 最简NFA构造法加子集构造法、位置自动机(followpos)、Brzozowski导数
+use std::time::{Duration, Instant};
+
+use regular_expression::char_set::CharSetTable;
+use regular_expression::graph::Graph;
+use regular_expression::pattern::{parse, PatternOptions};
+
+/// 重复构造的次数,取平均时间
+const ROUNDS: u32 = 20;
+
+fn measure(build: impl Fn(&mut CharSetTable) -> Graph) -> (i32, Duration) {
+    let mut states = 0;
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let mut table = CharSetTable::new();
+        states = build(&mut table).num_of_states;
+    }
+    (states, start.elapsed() / ROUNDS)
+}
+
+fn main() {
+    let patterns = [
+        "(a|b)*abb",
+        "(a|b)*a(a|b){4}",
+        "[a-zA-Z_][a-zA-Z0-9_]*",
+        "[0-9]+(\\.[0-9]+)?([eE][+-]?[0-9]+)?",
+        "\"([^\"\\\\]|\\\\.)*\"",
+        "(if|then|else|while|do|return)",
+    ];
+    println!(
+        "{:<40} {:>16} {:>16} {:>16}",
+        "pattern", "thompson+subset", "followpos", "derivative"
+    );
+    for pattern in patterns {
+        let ast = parse(pattern, &PatternOptions::default()).unwrap();
+        let results = [
+            measure(|table| ast.to_graph(table).unwrap().nfa_to_dfa(table)),
+            measure(|table| ast.to_position_dfa(table).unwrap()),
+            measure(|table| ast.simplify().to_dfa(table).unwrap()),
+        ];
+        let cells: Vec<String> = results
+            .iter()
+            .map(|(states, time)| format!("{} / {:.0?}", states, time))
+            .collect();
+        println!(
+            "{:<40} {:>16} {:>16} {:>16}",
+            pattern, cells[0], cells[1], cells[2]
+        );
+    }
+}
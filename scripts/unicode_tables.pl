@@ -0,0 +1,70 @@
+#!/usr/bin/env perl
+# 生成 src/unicode_tables.rs:Unicode通用类别、文字(Script)以及White_Space、Alphabetic属性的字符范围表,
+# 以及简单大小写折叠(Simple_Case_Folding)表
+# 用法: perl scripts/unicode_tables.pl > src/unicode_tables.rs
+use strict;
+use warnings;
+use Unicode::UCD qw(prop_invlist prop_invmap prop_values prop_value_aliases);
+
+# 把倒排列表转换为闭区间,并去掉char无法表示的代理区(D800..DFFF)
+sub ranges {
+    my @list = prop_invlist($_[0]);
+    my @ranges;
+    for (my $i = 0; $i < @list; $i += 2) {
+        my $from = $list[$i];
+        my $to = $i + 1 < @list ? $list[$i + 1] - 1 : 0x10FFFF;
+        for my $r ([$from, $to < 0xD7FF ? $to : 0xD7FF], [$from > 0xE000 ? $from : 0xE000, $to]) {
+            push @ranges, $r if $r->[0] <= $r->[1];
+        }
+    }
+    return @ranges;
+}
+
+sub table {
+    my ($name, @ranges) = @_;
+    my $out = "pub const $name: &[(char, char)] = &[\n";
+    my @items = map { sprintf("('\\u{%X}', '\\u{%X}')", @$_) } @ranges;
+    while (my @line = splice(@items, 0, 3)) {
+        $out .= "    " . join(", ", @line) . ",\n";
+    }
+    return $out . "];\n";
+}
+
+my (@tables, @index, @aliases);
+for my $kind (["gc", "GENERAL_CATEGORY"], ["sc", "SCRIPT"]) {
+    my ($prop, $index_name) = @$kind;
+    my @entries;
+    for my $value (sort(prop_values($prop))) {
+        my ($short, $long) = prop_value_aliases($prop, $value);
+        # Katakana_Or_Hiragana等没有字符的值没有别名
+        next unless defined $short;
+        my @ranges = ranges("$prop=$short");
+        next unless @ranges;
+        my $const = uc("${prop}_$long");
+        push @tables, table($const, @ranges);
+        push @entries, "    (\"$short\", $const),\n";
+        push @aliases, "    (\"$long\", \"$short\"),\n" if $long ne $short;
+    }
+    push @index, "pub const $index_name: &[(&str, &[(char, char)])] = &[\n" . join("", @entries) . "];\n";
+}
+push @tables, table("WHITE_SPACE", ranges("White_Space"));
+push @tables, table("ALPHABETIC", ranges("Alphabetic"));
+
+# 简单大小写折叠:(字符, 折叠后的字符),只列出折叠后不等于自身的字符,按字符排序
+my ($list, $map) = prop_invmap("Simple_Case_Folding");
+my @folding;
+for my $i (0 .. $#$list - 1) {
+    next unless $map->[$i];
+    for my $cp ($list->[$i] .. $list->[$i + 1] - 1) {
+        push @folding, [$cp, $map->[$i] + $cp - $list->[$i]];
+    }
+}
+push @tables, "/// 简单大小写折叠:(字符, 折叠后的字符),按字符排序\n"
+    . table("SIMPLE_CASE_FOLDING", @folding);
+
+print "// 由 scripts/unicode_tables.pl 根据 Unicode ", Unicode::UCD::UnicodeVersion(), " 生成,请勿手工修改\n\n";
+print "/// 生成本文件所用的Unicode版本\n";
+print "pub const UNICODE_VERSION: &str = \"", Unicode::UCD::UnicodeVersion(), "\";\n\n";
+print "/// 通用类别与文字的全称 -> 简称\n";
+print "pub const VALUE_ALIASES: &[(&str, &str)] = &[\n", @aliases, "];\n\n";
+print join("\n", @index), "\n", join("\n", @tables);
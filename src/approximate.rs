@@ -0,0 +1,136 @@
+use super::char_set::CharSetTable;
+use super::graph::{Category, DriverType, Graph, StateType};
+
+impl<C: Category> Graph<C> {
+    /// **近似匹配**:允许至多max_edits次编辑(插入、删除或替换一个字符)时,
+    /// 输入串能否被该图接受,能时返回最少的编辑次数(Levenshtein距离)。
+    /// 在NFA模拟的基础上为每个状态记录到达它的最少编辑次数:
+    /// - 边上的字符与输入字符相同时沿边转换,代价为0
+    /// - 沿任意非ε边转换并读入一个输入字符,代价为1(替换)
+    /// - 读入一个输入字符而停在原状态,代价为1(输入中多了一个字符)
+    /// - 沿任意非ε边转换而不读入字符,代价为1(输入中少了一个字符)
+    pub fn approximate_match(
+        &self,
+        input: &str,
+        max_edits: usize,
+        table: &CharSetTable,
+    ) -> Option<usize> {
+        let mut costs: Vec<Option<usize>> = vec![None; self.p_state_table.len()];
+        costs[0] = Some(0);
+        self.edit_closure(&mut costs, max_edits);
+        for c in input.chars() {
+            let mut next: Vec<Option<usize>> =
+                costs.iter().map(|cost| cost.map(|cost| cost + 1)).collect();
+            for edge in self.p_edge_table.iter() {
+                let cost = match costs[edge.from_state as usize] {
+                    Some(cost) => cost,
+                    None => continue,
+                };
+                let accepted = match edge.driver_type {
+                    DriverType::NULL => continue,
+                    DriverType::CHAR => edge.driver_id == c as i32,
+                    DriverType::CHARSET => table.contains_char(edge.driver_id, c),
+                };
+                let cost = if accepted { cost } else { cost + 1 };
+                relax(&mut next[edge.next_state as usize], cost);
+            }
+            for cost in next.iter_mut() {
+                if cost.is_some_and(|cost| cost > max_edits) {
+                    *cost = None;
+                }
+            }
+            self.edit_closure(&mut next, max_edits);
+            if next.iter().all(|cost| cost.is_none()) {
+                return None;
+            }
+            costs = next;
+        }
+        self.p_state_table
+            .iter()
+            .filter(|state| state.state_type == StateType::MATCH)
+            .filter_map(|state| costs[state.state_id as usize])
+            .min()
+    }
+
+    /// 不读入字符的转换:ε边代价为0,其他边代价为1(删除),
+    /// 反复松弛直到各状态的代价不再减少,代价超过max_edits的状态不加入
+    fn edit_closure(&self, costs: &mut [Option<usize>], max_edits: usize) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for edge in self.p_edge_table.iter() {
+                let cost = match costs[edge.from_state as usize] {
+                    Some(cost) => cost,
+                    None => continue,
+                };
+                let cost = match edge.driver_type {
+                    DriverType::NULL => cost,
+                    _ => cost + 1,
+                };
+                if cost <= max_edits {
+                    changed |= relax(&mut costs[edge.next_state as usize], cost);
+                }
+            }
+        }
+    }
+}
+
+/// 用cost更新状态的代价,代价减少时返回true
+fn relax(slot: &mut Option<usize>, cost: usize) -> bool {
+    match *slot {
+        Some(old) if old <= cost => false,
+        _ => {
+            *slot = Some(cost);
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::compile;
+
+    /// 与关键字的编辑距离
+    #[test]
+    fn test_approximate_match() {
+        let mut table = CharSetTable::new();
+        let keyword = compile("if|else|while|return", &mut table).unwrap();
+        let cases = [
+            ("while", Some(0)),
+            ("iff", Some(1)),
+            ("els", Some(1)),
+            ("wile", Some(1)),
+            ("retarn", Some(1)),
+            ("retrun", Some(2)),
+            ("esle", Some(2)),
+            ("x", Some(2)),
+            ("", Some(2)),
+            ("function", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                keyword.approximate_match(input, 2, &table),
+                expected,
+                "{}",
+                input
+            );
+        }
+        assert_eq!(keyword.approximate_match("retrun", 1, &table), None);
+    }
+
+    /// 带闭包与字符集的图
+    #[test]
+    fn test_approximate_match_loops() {
+        let mut table = CharSetTable::new();
+        let number = compile("[0-9]+(\\.[0-9]+)?", &mut table).unwrap();
+        assert_eq!(number.approximate_match("3.14", 1, &table), Some(0));
+        assert_eq!(number.approximate_match("3,14", 1, &table), Some(1));
+        assert_eq!(number.approximate_match("3.", 1, &table), Some(1));
+        assert_eq!(number.approximate_match("x12345678", 1, &table), Some(1));
+        assert_eq!(number.approximate_match("3..14", 0, &table), None);
+        let id = compile("[a-z]+", &mut table).unwrap();
+        assert_eq!(id.approximate_match("", 3, &table), Some(1));
+        assert_eq!(id.approximate_match("a1b2", 3, &table), Some(2));
+    }
+}
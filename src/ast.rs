@@ -0,0 +1,717 @@
+use std::fmt::{Display, Write};
+
+use super::char_set::{complement_ranges, normalize_ranges, CharSetTable};
+use super::graph::{DriverType, Graph, LexemeCategory, State, StateType};
+use super::pattern::PatternError;
+
+/// 正则表达式的**抽象语法树**,是正则表达式文本与NFA之间的中间表示
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Ast {
+    /// 空串ε
+    Empty,
+    /// 单个字符
+    Literal(char),
+    /// 字符类,字符范围已排序合并;空的字符类表示空语言,输出为`[^\s\S]`
+    Class(Vec<(char, char)>),
+    /// 连接
+    Concat(Vec<Ast>),
+    /// 选择
+    Alternation(Vec<Ast>),
+    /// 重复min到max次,max为None时不限次数
+    Repeat {
+        ast: Box<Ast>,
+        min: u32,
+        max: Option<u32>,
+    },
+    /// 括号
+    Group(Box<Ast>),
+    /// 交:同时被各项接受的串
+    Intersection(Vec<Ast>),
+    /// 补:不被该项接受的串
+    Complement(Box<Ast>),
+    /// 断言
+    Assertion(AssertionKind),
+}
+
+/// 断言的类型
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AssertionKind {
+    /// `^`:输入的开头
+    StartText,
+    /// `$`:输入的结尾
+    EndText,
+    /// `\b`:单词边界
+    WordBoundary,
+    /// `\B`:非单词边界
+    NotWordBoundary,
+}
+
+impl Ast {
+    /// 字符类,空的字符类仍然是字符类,单个字符化为Literal由simplify完成
+    pub fn class(ranges: &[(char, char)]) -> Ast {
+        let mut ranges = ranges.to_vec();
+        normalize_ranges(&mut ranges);
+        Ast::Class(ranges)
+    }
+    /// 重复
+    pub fn repeat(ast: Ast, min: u32, max: Option<u32>) -> Ast {
+        Ast::Repeat {
+            ast: Box::new(ast),
+            min,
+            max,
+        }
+    }
+
+    /// 代数化简:去掉括号、展平嵌套的连接与选择、把选择中的单个字符与字符类合并为一个字符类、
+    /// 合并嵌套的`*`、`+`、`?`(如`(a*)*`化为`a*`)、把`x|ε`化为`x?`、提取选择项的公共前后缀、把`xx*`化为`x+`,
+    /// 化简前后描述的语言相同
+    pub fn simplify(&self) -> Ast {
+        match self {
+            Ast::Empty | Ast::Literal(_) | Ast::Assertion(_) => self.clone(),
+            Ast::Class(ranges) => match ranges[..] {
+                [(from_char, to_char)] if from_char == to_char => Ast::Literal(from_char),
+                _ => self.clone(),
+            },
+            // 没有捕获分组,括号只影响优先级
+            Ast::Group(ast) => ast.simplify(),
+            Ast::Concat(items) => {
+                let mut result = Vec::new();
+                for item in items.iter() {
+                    match item.simplify() {
+                        Ast::Empty => {}
+                        Ast::Concat(inner) => result.extend(inner),
+                        item => result.push(item),
+                    }
+                }
+                merge_plus(&mut result);
+                match result.len() {
+                    0 => Ast::Empty,
+                    1 => result.pop().unwrap(),
+                    _ => Ast::Concat(result),
+                }
+            }
+            Ast::Alternation(items) => {
+                let mut flat = Vec::new();
+                for item in items.iter() {
+                    match item.simplify() {
+                        Ast::Alternation(inner) => flat.extend(inner),
+                        item => flat.push(item),
+                    }
+                }
+                // 单个字符与字符类合并为一个字符类,放在第一个这样的选择项的位置
+                let mut ranges: Vec<(char, char)> = Vec::new();
+                let mut class_pos: Option<usize> = None;
+                let mut result: Vec<Ast> = Vec::new();
+                for item in flat {
+                    match item {
+                        Ast::Literal(c) => ranges.push((c, c)),
+                        Ast::Class(class) => ranges.extend(class),
+                        item => {
+                            if !result.contains(&item) {
+                                result.push(item);
+                            }
+                            continue;
+                        }
+                    }
+                    if class_pos.is_none() {
+                        class_pos = Some(result.len());
+                    }
+                }
+                if let Some(pos) = class_pos {
+                    result.insert(pos, Ast::class(&ranges).simplify());
+                }
+                if let Some(factored) = factor_alternatives(&result) {
+                    return factored;
+                }
+                // x|ε化为x?
+                if result.len() > 1 && result.contains(&Ast::Empty) {
+                    result.retain(|item| *item != Ast::Empty);
+                    let rest = match result.len() {
+                        1 => result.pop().unwrap(),
+                        _ => Ast::Alternation(result),
+                    };
+                    return Ast::repeat(rest, 0, Some(1)).simplify();
+                }
+                match result.len() {
+                    1 => result.pop().unwrap(),
+                    _ => Ast::Alternation(result),
+                }
+            }
+            Ast::Intersection(items) => {
+                let mut result = Vec::new();
+                for item in items.iter() {
+                    match item.simplify() {
+                        Ast::Intersection(inner) => result.extend(inner),
+                        item => {
+                            if !result.contains(&item) {
+                                result.push(item);
+                            }
+                        }
+                    }
+                }
+                match result.len() {
+                    1 => result.pop().unwrap(),
+                    _ => Ast::Intersection(result),
+                }
+            }
+            Ast::Complement(ast) => match ast.simplify() {
+                Ast::Complement(inner) => *inner,
+                ast => Ast::Complement(Box::new(ast)),
+            },
+            Ast::Repeat { ast, min, max } => {
+                let ast = ast.simplify();
+                // (x?|y)+与(x*|y)*化为(x|y)*
+                if let (Ast::Alternation(items), 0 | 1, None) = (&ast, *min, *max) {
+                    if items.iter().any(is_optional_loop) {
+                        let items = items
+                            .iter()
+                            .map(|item| match item {
+                                Ast::Repeat { ast, .. } if is_optional_loop(item) => *ast.clone(),
+                                item => item.clone(),
+                            })
+                            .collect();
+                        return Ast::repeat(Ast::Alternation(items), 0, None).simplify();
+                    }
+                }
+                match (ast, *min, *max) {
+                    (_, 0, Some(0)) | (Ast::Empty, _, _) => Ast::Empty,
+                    (ast, 1, Some(1)) => ast,
+                    // x,y∈{*,+,?}时(x)y的最少次数为两者之积,有一个不限次数则不限次数
+                    (
+                        Ast::Repeat {
+                            ast,
+                            min: inner_min,
+                            max: inner_max,
+                        },
+                        min,
+                        max,
+                    ) if inner_min <= 1
+                        && min <= 1
+                        && matches!(inner_max, None | Some(1))
+                        && matches!(max, None | Some(1)) =>
+                    {
+                        let max = if inner_max.is_none() || max.is_none() {
+                            None
+                        } else {
+                            Some(1)
+                        };
+                        Ast::repeat(*ast, inner_min * min, max)
+                    }
+                    (ast, min, max) => Ast::repeat(ast, min, max),
+                }
+            }
+        }
+    }
+
+    /// 用最简NFA构造法生成NFA,字符类加入到table中;
+    /// 自动机总是从输入的开头匹配到结尾,`^`与`$`生成空转换,
+    /// 调用者需保证它们只出现在正则表达式的两端。单词边界断言无法生成NFA,返回错误,
+    /// 抽象语法树中没有位置信息,错误的position为0。
+    /// 交与补先确定化再做DFA上的运算
+    pub fn to_graph(&self, table: &mut CharSetTable) -> Result<Graph, PatternError> {
+        Ok(match self {
+            Ast::Empty => Graph::generate_basic_nfa(DriverType::NULL, -1),
+            Ast::Literal(c) => Graph::generate_basic_nfa(DriverType::CHAR, *c as i32),
+            Ast::Class(ranges) => match ranges[..] {
+                [] => empty_language_graph(),
+                [(from_char, to_char)] if from_char == to_char => {
+                    Graph::generate_basic_nfa(DriverType::CHAR, from_char as i32)
+                }
+                _ => Graph::generate_basic_nfa(DriverType::CHARSET, table.from_ranges(ranges)),
+            },
+            Ast::Concat(items) => {
+                let mut graph: Option<Graph> = None;
+                for item in items.iter() {
+                    let item = item.to_graph(table)?;
+                    graph = Some(match graph {
+                        Some(graph) => graph.product(&item),
+                        None => item,
+                    });
+                }
+                graph.unwrap_or_else(|| Graph::generate_basic_nfa(DriverType::NULL, -1))
+            }
+            Ast::Alternation(items) => {
+                let mut graph: Option<Graph> = None;
+                for item in items.iter() {
+                    let item = item.to_graph(table)?;
+                    graph = Some(match graph {
+                        Some(graph) => graph.union(&item),
+                        None => item,
+                    });
+                }
+                graph.unwrap_or_else(empty_language_graph)
+            }
+            Ast::Repeat { ast, min, max } => repeat_graph(&ast.to_graph(table)?, *min, *max),
+            Ast::Group(ast) => ast.to_graph(table)?,
+            Ast::Assertion(AssertionKind::StartText | AssertionKind::EndText) => {
+                Graph::generate_basic_nfa(DriverType::NULL, -1)
+            }
+            Ast::Assertion(_) => {
+                return Err(PatternError {
+                    position: 0,
+                    message: format!("assertion {} cannot be compiled into an NFA", self),
+                })
+            }
+            // 交与补在DFA上用乘积构造法与完全DFA求得,再改写为最简NFA构造法要求的形式
+            Ast::Intersection(items) => {
+                let mut graph: Option<Graph> = None;
+                for item in items.iter() {
+                    let item = item.to_graph(table)?;
+                    graph = Some(match graph {
+                        Some(graph) => graph.intersect(&item, table).dfa_to_nfa(),
+                        None => item,
+                    });
+                }
+                graph.unwrap_or_else(|| Graph::generate_basic_nfa(DriverType::NULL, -1))
+            }
+            Ast::Complement(ast) => ast.to_graph(table)?.complement(table).dfa_to_nfa(),
+        })
+    }
+
+    /// 作为连接、选择或重复的子项输出时是否需要加括号
+    fn needs_group(&self, parent: &Ast) -> bool {
+        match parent {
+            Ast::Concat(_) => matches!(
+                self,
+                Ast::Empty | Ast::Concat(_) | Ast::Alternation(_) | Ast::Intersection(_)
+            ),
+            Ast::Alternation(_) => matches!(self, Ast::Alternation(_)),
+            Ast::Intersection(_) => matches!(
+                self,
+                Ast::Empty | Ast::Alternation(_) | Ast::Intersection(_)
+            ),
+            Ast::Repeat { .. } => matches!(
+                self,
+                Ast::Empty
+                    | Ast::Concat(_)
+                    | Ast::Alternation(_)
+                    | Ast::Repeat { .. }
+                    | Ast::Assertion(_)
+                    | Ast::Intersection(_)
+                    | Ast::Complement(_)
+            ),
+            Ast::Complement(_) => matches!(
+                self,
+                Ast::Empty | Ast::Concat(_) | Ast::Alternation(_) | Ast::Intersection(_)
+            ),
+            _ => false,
+        }
+    }
+    /// 输出子项,需要时加非捕获括号(?:...)
+    fn fmt_child(&self, parent: &Ast, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.needs_group(parent) {
+            write!(f, "(?:{})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+/// 输出为正则表达式文本,打开`PatternOptions::set_operations`重新分析得到相同的抽象语法树
+impl Display for Ast {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Ast::Empty => Ok(()),
+            Ast::Literal(c) => write_char(f, *c, false),
+            Ast::Class(ranges) => {
+                if *ranges == complement_ranges(&[('\n', '\n')]) {
+                    return f.write_char('.');
+                }
+                if ranges.is_empty() {
+                    return f.write_str("[^\\s\\S]");
+                }
+                // 取范围较少的写法
+                let complement = complement_ranges(ranges);
+                let (negated, ranges) = if !ranges.is_empty() && complement.len() < ranges.len() {
+                    (true, &complement)
+                } else {
+                    (false, ranges)
+                };
+                f.write_str(if negated { "[^" } else { "[" })?;
+                for &(from_char, to_char) in ranges.iter() {
+                    write_char(f, from_char, true)?;
+                    if from_char as u32 + 1 == to_char as u32 {
+                        write_char(f, to_char, true)?;
+                    } else if from_char != to_char {
+                        f.write_char('-')?;
+                        write_char(f, to_char, true)?;
+                    }
+                }
+                f.write_char(']')
+            }
+            Ast::Concat(items) => {
+                for item in items.iter() {
+                    item.fmt_child(self, f)?;
+                }
+                Ok(())
+            }
+            Ast::Alternation(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        f.write_char('|')?;
+                    }
+                    item.fmt_child(self, f)?;
+                }
+                Ok(())
+            }
+            Ast::Repeat { ast, min, max } => {
+                ast.fmt_child(self, f)?;
+                match (min, max) {
+                    (0, None) => f.write_char('*'),
+                    (1, None) => f.write_char('+'),
+                    (0, Some(1)) => f.write_char('?'),
+                    (min, None) => write!(f, "{{{},}}", min),
+                    (min, Some(max)) if min == max => write!(f, "{{{}}}", min),
+                    (min, Some(max)) => write!(f, "{{{},{}}}", min, max),
+                }
+            }
+            Ast::Group(ast) => write!(f, "({})", ast),
+            Ast::Intersection(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        f.write_char('&')?;
+                    }
+                    item.fmt_child(self, f)?;
+                }
+                Ok(())
+            }
+            Ast::Complement(ast) => {
+                f.write_char('~')?;
+                ast.fmt_child(self, f)
+            }
+            Ast::Assertion(kind) => f.write_str(match kind {
+                AssertionKind::StartText => "^",
+                AssertionKind::EndText => "$",
+                AssertionKind::WordBoundary => "\\b",
+                AssertionKind::NotWordBoundary => "\\B",
+            }),
+        }
+    }
+}
+
+/// 输出一个字符,必要时转义;in_class为true时按方括号内的规则转义
+fn write_char(f: &mut std::fmt::Formatter<'_>, c: char, in_class: bool) -> std::fmt::Result {
+    let special = if in_class {
+        matches!(c, '\\' | ']' | '[' | '^' | '-')
+    } else {
+        matches!(
+            c,
+            '\\' | '.'
+                | '+'
+                | '*'
+                | '?'
+                | '('
+                | ')'
+                | '|'
+                | '['
+                | ']'
+                | '{'
+                | '}'
+                | '^'
+                | '$'
+                | '&'
+                | '~'
+        )
+    };
+    match c {
+        _ if special => write!(f, "\\{}", c),
+        '\n' => f.write_str("\\n"),
+        '\t' => f.write_str("\\t"),
+        '\r' => f.write_str("\\r"),
+        c if c.is_control() || (c.is_whitespace() && c != ' ') => {
+            write!(f, "\\x{{{:X}}}", c as u32)
+        }
+        c => f.write_char(c),
+    }
+}
+
+/// 提取所有选择项的公共前缀或公共后缀:xy|xz化为x(?:y|z),yx|zx化为(?:y|z)x
+fn factor_alternatives(items: &[Ast]) -> Option<Ast> {
+    if items.len() < 2 {
+        return None;
+    }
+    let sequences: Vec<&[Ast]> = items
+        .iter()
+        .map(|item| match item {
+            Ast::Concat(inner) => inner.as_slice(),
+            item => std::slice::from_ref(item),
+        })
+        .collect();
+    let min_len = sequences
+        .iter()
+        .map(|sequence| sequence.len())
+        .min()
+        .unwrap();
+    let first = sequences[0];
+    let prefix = (0..min_len)
+        .take_while(|&i| sequences.iter().all(|sequence| sequence[i] == first[i]))
+        .count();
+    let suffix = (0..min_len - prefix)
+        .take_while(|&i| {
+            let expected = &first[first.len() - 1 - i];
+            sequences
+                .iter()
+                .all(|sequence| sequence[sequence.len() - 1 - i] == *expected)
+        })
+        .count();
+    if prefix == 0 && suffix == 0 {
+        return None;
+    }
+    let rests = sequences
+        .iter()
+        .map(|sequence| Ast::Concat(sequence[prefix..sequence.len() - suffix].to_vec()))
+        .collect();
+    let mut concat = first[..prefix].to_vec();
+    concat.push(Ast::Alternation(rests));
+    concat.extend_from_slice(&first[first.len() - suffix..]);
+    Some(Ast::Concat(concat).simplify())
+}
+
+/// 是否为x?或x*
+fn is_optional_loop(ast: &Ast) -> bool {
+    matches!(
+        ast,
+        Ast::Repeat {
+            min: 0,
+            max: None | Some(1),
+            ..
+        }
+    )
+}
+
+/// 在展平的连接中把xx*与x*x合并为x+(x可以是一段连接),把相邻的同一子项的重复合并,如x?x*化为x*
+fn merge_plus(items: &mut Vec<Ast>) {
+    let mut index = 0;
+    while index < items.len() {
+        if let Ast::Repeat {
+            ast,
+            min: 0,
+            max: None,
+        } = &items[index]
+        {
+            let len = match ast.as_ref() {
+                Ast::Concat(inner) => inner.len(),
+                _ => 1,
+            };
+            // 与y*相邻的一段w满足w*与y*相同时,wy*与y*w都化为w+
+            let star = items[index].clone();
+            let window_plus = |window: &[Ast]| {
+                let window = match window {
+                    [item] => item.clone(),
+                    window => Ast::Concat(window.to_vec()),
+                };
+                (Ast::repeat(window.clone(), 0, None).simplify() == star)
+                    .then(|| Ast::repeat(window, 1, None).simplify())
+            };
+            if index >= len {
+                if let Some(plus) = window_plus(&items[index - len..index]) {
+                    items.splice(index - len..=index, [plus]);
+                    index -= len;
+                    continue;
+                }
+            }
+            if items.len() > index + len {
+                if let Some(plus) = window_plus(&items[index + 1..=index + len]) {
+                    items.splice(index..=index + len, [plus]);
+                    continue;
+                }
+            }
+        }
+        // x{a,}x{b,c}与x{a,b}x{c,}化为x{a+c,}或x{a+b,}
+        if let (
+            Some(Ast::Repeat {
+                ast,
+                min,
+                max: first_max,
+            }),
+            Some(Ast::Repeat {
+                ast: next_ast,
+                min: next_min,
+                max: next_max,
+            }),
+        ) = (items.get(index), items.get(index + 1))
+        {
+            if ast == next_ast && (first_max.is_none() || next_max.is_none()) {
+                let merged = Ast::repeat(*ast.clone(), min + next_min, None).simplify();
+                items.splice(index..=index + 1, [merged]);
+                continue;
+            }
+        }
+        index += 1;
+    }
+}
+
+/// 不接受任何串的NFA:开始状态与结束状态之间没有边
+fn empty_language_graph() -> Graph {
+    Graph {
+        graph_id: 0,
+        num_of_states: 2,
+        p_edge_table: Vec::new(),
+        p_state_table: vec![
+            State {
+                state_id: 0,
+                state_type: StateType::UNMATCH,
+                category: LexemeCategory::EMPTY,
+            },
+            State {
+                state_id: 1,
+                state_type: StateType::MATCH,
+                category: LexemeCategory::EMPTY,
+            },
+        ],
+    }
+}
+
+/// 计数重复:连接min个graph,再连接max-min个graph?;max为None时连接min-1个graph与graph+
+fn repeat_graph(graph: &Graph, min: u32, max: Option<u32>) -> Graph {
+    let mut items: Vec<Graph> = Vec::new();
+    match max {
+        None if min > 0 => {
+            for _ in 1..min {
+                items.push(graph.clone());
+            }
+            items.push(graph.plus_closure());
+        }
+        None => items.push(graph.closure()),
+        Some(max) => {
+            for _ in 0..min {
+                items.push(graph.clone());
+            }
+            for _ in min..max {
+                items.push(graph.zero_or_one());
+            }
+        }
+    }
+    items
+        .into_iter()
+        .reduce(|result, item| result.product(&item))
+        .unwrap_or_else(|| Graph::generate_basic_nfa(DriverType::NULL, -1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::parse;
+    use crate::test_util::set_operations;
+
+    fn parse_ast(pattern: &str) -> Ast {
+        parse(pattern, &set_operations()).unwrap()
+    }
+
+    /// 输出的正则表达式重新分析后得到相同的抽象语法树
+    #[test]
+    fn test_round_trip() {
+        let patterns = [
+            "(a|b)*abb",
+            "a(?:b|c)d",
+            "(?:ab)c",
+            "(?:a*)*",
+            "a{2}b{2,}c{2,5}",
+            "[a-z_][a-z0-9_]*",
+            "[^a-c]",
+            ".\\.\\n\\t\\x{7}",
+            "\\[\\]\\{\\}\\(\\)\\|\\^\\$",
+            "[\\]\\[\\-\\^\\\\]",
+            "^a|b$",
+            "\\bword\\B",
+            "x|",
+            "(?i)select",
+            "\\d+\\p{Greek}",
+            "a(?:)b",
+            "[a-z]+&~(?:if|else)",
+            "~a*|(?:~a)*",
+            "x(?:a|b)&y\\&\\~",
+            "~~a",
+            "[^\\s\\S]",
+            "a[^\\s\\S]*|b",
+        ];
+        for pattern in patterns {
+            let ast = parse_ast(pattern);
+            let printed = ast.to_string();
+            assert_eq!(parse_ast(&printed), ast, "{} -> {}", pattern, printed);
+        }
+        assert_eq!(parse_ast("(a|b)*abb").to_string(), "(a|b)*abb");
+        assert_eq!(parse_ast("[^a-c]").to_string(), "[^a-c]");
+        assert_eq!(parse_ast(".").to_string(), ".");
+    }
+
+    /// 代数化简
+    #[test]
+    fn test_simplify() {
+        let cases = [
+            ("(a|b)|(c|d)", "[a-d]"),
+            ("a|[0-9]|bc|b", "[0-9ab]|bc"),
+            ("((ab))c", "abc"),
+            ("(a*)*", "a*"),
+            ("(a+)*", "a*"),
+            ("(a+)+", "a+"),
+            ("(a?)?", "a?"),
+            ("(a+)?", "a*"),
+            ("(a{2})*", "(?:a{2})*"),
+            ("a{1}b{0}", "a"),
+            ("ab|ab|c", "ab|c"),
+            ("[x]", "x"),
+            ("a|", "a?"),
+            ("ab|(?:)|c", "(?:ab|c)?"),
+            ("aa*", "a+"),
+            ("a*a", "a+"),
+            ("ab(ab)*c", "(?:ab)+c"),
+            ("a*a*", "a*"),
+            ("a?a*b+b*", "a*b+"),
+            ("(c?|ab)+", "(?:c|ab)*"),
+            ("ab|ac", "a[bc]"),
+            ("xay|xbcy", "x(?:a|bc)y"),
+            ("ab|a", "ab?"),
+        ];
+        for (pattern, expected) in cases {
+            assert_eq!(
+                parse_ast(pattern).simplify().to_string(),
+                expected,
+                "{}",
+                pattern
+            );
+        }
+    }
+
+    /// 化简前后生成的NFA接受相同的串
+    #[test]
+    fn test_simplify_preserves_language() {
+        let patterns = [
+            "(a|b)|(c|d)",
+            "((a+)?b)*",
+            "(a?)+c",
+            "a{2,}|b{1,3}",
+            "x(?:)y",
+        ];
+        let inputs = [
+            "", "a", "b", "ab", "aab", "abab", "c", "ac", "aaaa", "bbb", "bbbb", "xy",
+        ];
+        for pattern in patterns {
+            let ast = parse_ast(pattern);
+            let mut table = CharSetTable::new();
+            let graph = ast.to_graph(&mut table).unwrap();
+            let simplified = ast.simplify().to_graph(&mut table).unwrap();
+            for input in inputs {
+                assert_eq!(
+                    graph.simulate(input, &table),
+                    simplified.simulate(input, &table),
+                    "{} on {:?}",
+                    pattern,
+                    input
+                );
+            }
+        }
+    }
+
+    /// 单词边界断言无法生成NFA,返回错误而不是panic
+    #[test]
+    fn test_assertion_to_graph() {
+        let mut table = CharSetTable::new();
+        let error = parse_ast(r"a\bb").to_graph(&mut table).unwrap_err();
+        assert_eq!(
+            error.message,
+            r"assertion \b cannot be compiled into an NFA"
+        );
+        assert!(parse_ast("^ab$").to_graph(&mut table).is_ok());
+    }
+}
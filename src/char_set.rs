@@ -7,8 +7,10 @@ use std::{
     ops::{Deref, DerefMut},
 };
 
+use super::unicode_tables;
+
 /// **字符集**的数据结构
-#[derive(Debug,Clone)]
+#[derive(Debug, Clone)]
 pub struct CharSet {
     //字符集id
     pub index_id: i32,
@@ -141,7 +143,7 @@ impl UnionFunc<i32, i32> for CharSetTable {
             None => 0,
         };
         let len = char_set1.len() as i32;
-        for (i,&value) in char_set1.iter().enumerate() {
+        for (i, &value) in char_set1.iter().enumerate() {
             let from_char = self[value as usize].from_char;
             let to_char = self[value as usize].to_char;
             self.push(CharSet {
@@ -151,7 +153,7 @@ impl UnionFunc<i32, i32> for CharSetTable {
                 to_char,
             });
         }
-        for (i,&value) in char_set2.iter().enumerate() {
+        for (i, &value) in char_set2.iter().enumerate() {
             let from_char = self[value as usize].from_char;
             let to_char = self[value as usize].to_char;
             self.push(CharSet {
@@ -165,24 +167,35 @@ impl UnionFunc<i32, i32> for CharSetTable {
     }
 }
 
+impl Default for CharSetTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CharSetTable {
     /// 构造函数，初始化为空数组
     pub fn new() -> CharSetTable {
         CharSetTable { table: vec![] }
     }
-    pub fn copy_by_index_id(&mut self,index_id:i32)->i32{
-        let mut copy_index:Vec<usize> = vec![];
-        let next_index = match self.get_max(){
-            Some(value) => value+1,
-            None=>0,
+    /// 判断字符c是否属于index_id对应的字符集
+    pub fn contains_char(&self, index_id: i32, c: char) -> bool {
+        self.iter()
+            .any(|ele| ele.index_id == index_id && ele.from_char <= c && c <= ele.to_char)
+    }
+    pub fn copy_by_index_id(&mut self, index_id: i32) -> i32 {
+        let mut copy_index: Vec<usize> = vec![];
+        let next_index = match self.get_max() {
+            Some(value) => value + 1,
+            None => 0,
         };
-        for i in 0..self.len(){
-            if self[i].index_id == index_id{
+        for i in 0..self.len() {
+            if self[i].index_id == index_id {
                 copy_index.push(i);
             }
         }
-        for item in copy_index{
-            let copy = CharSet{
+        for item in copy_index {
+            let copy = CharSet {
                 index_id: next_index,
                 ..self[item].clone()
             };
@@ -192,12 +205,12 @@ impl CharSetTable {
     }
     /// 获取字符表中的index_id的最大值
     fn get_max(&self) -> Option<i32> {
-        if self.len() == 0 {
+        if self.is_empty() {
             return None;
         }
         let mut max = -1;
         for ele in self.iter() {
-            let curr = (*ele).index_id;
+            let curr = ele.index_id;
             if curr > max {
                 max = curr;
             }
@@ -208,8 +221,8 @@ impl CharSetTable {
     fn get_max_segment_id(&self, index_id: i32) -> Option<i32> {
         let mut max_segment_id = -1;
         for ele in self.iter() {
-            if (*ele).index_id == index_id {
-                let curr_segment_id = (*ele).segment_id;
+            if ele.index_id == index_id {
+                let curr_segment_id = ele.segment_id;
                 if curr_segment_id > max_segment_id {
                     max_segment_id = curr_segment_id;
                 }
@@ -243,7 +256,7 @@ impl CharSetTable {
         });
         index_id
     }
-    /// 字符集与字符之间的差运算,改变输入的字符集 
+    /// 字符集与字符之间的差运算,改变输入的字符集
     pub fn difference(&mut self, char_set_id: i32, c: char) -> i32 {
         // the new CharSet was added on this line
         let char_set_id = self.copy_by_index_id(char_set_id);
@@ -253,10 +266,10 @@ impl CharSetTable {
         let mut to_add_vec = Vec::new();
         for (index, ele) in self.iter_mut().enumerate() {
             //当前遍历到的index_id值
-            let index_id = (*ele).index_id;
+            let index_id = ele.index_id;
             if index_id == char_set_id {
                 //如果c在该段的范围中,分情况讨论进行删除
-                let (from_char, to_char) = ((*ele).from_char, (*ele).to_char);
+                let (from_char, to_char) = (ele.from_char, ele.to_char);
                 if from_char <= c && to_char >= c {
                     //该段为一个字符,删除该段即可,加入到待删除数组中
                     if from_char == to_char {
@@ -266,11 +279,11 @@ impl CharSetTable {
                     } else {
                         //该字符刚好等于这个段的范围起始字符,更改该段的起始字符为下一个字符即可
                         if c == from_char {
-                            (*ele).from_char = char::from_u32(from_char as u32 + 1)
+                            ele.from_char = char::from_u32(from_char as u32 + 1)
                                 .expect("cannot increment this char");
                         //该字符刚好等于这个段的范围结尾字符,更改该段的起始字符为上一个字符即可
                         } else if c == to_char {
-                            (*ele).to_char =
+                            ele.to_char =
                                 char::from_u32(to_char as u32 - 1).expect("cannot decrease");
                         //该字符刚好处于在这个段的范围的中间，将该字符集分裂
                         //加入两个新的CharSet，删除原来的那一个CharSet即当前的index索引对应的CharSet
@@ -305,23 +318,381 @@ impl CharSetTable {
             self.remove(value);
         }
         //先加到末尾
-        for i in 0..to_add_vec.len() {
-            to_add_vec[i].segment_id = match self.get_max_segment_id(to_add_vec[i].index_id) {
+        for mut item in to_add_vec {
+            item.segment_id = match self.get_max_segment_id(item.index_id) {
                 Some(value) => value + 1,
                 None => 0,
             };
-            self.push(CharSet { ..to_add_vec[i] });
+            self.push(item);
         }
         char_set_id
     }
 }
 
+impl CharSetTable {
+    /// 取出index_id对应字符集的所有段,按起始字符排序并合并相交或相邻的段
+    pub fn ranges(&self, index_id: i32) -> Vec<(char, char)> {
+        let mut ranges: Vec<(char, char)> = self
+            .iter()
+            .filter(|ele| ele.index_id == index_id)
+            .map(|ele| (ele.from_char, ele.to_char))
+            .collect();
+        normalize_ranges(&mut ranges);
+        ranges
+    }
+    /// 由若干个字符范围生成一个新的字符集,每个范围作为一个段,加入到字符集表中
+    pub fn from_ranges(&mut self, ranges: &[(char, char)]) -> i32 {
+        let mut ranges = ranges.to_vec();
+        normalize_ranges(&mut ranges);
+        let index_id = match self.get_max() {
+            Some(value) => value + 1,
+            None => 0,
+        };
+        for (segment_id, &(from_char, to_char)) in ranges.iter().enumerate() {
+            self.push(CharSet {
+                index_id,
+                segment_id: segment_id as i32,
+                from_char,
+                to_char,
+            });
+        }
+        index_id
+    }
+    /// Unicode属性字符集,如`L`、`Letter`、`gc=Nd`、`Greek`、`sc=Latn`、`White_Space`、`Alphabetic`,
+    /// 名字的比较忽略大小写、空格、`_`和`-`;生成一个新的字符集加入到字符集表中,未知的属性返回None
+    pub fn unicode_property(&mut self, name: &str) -> Option<i32> {
+        let ranges = lookup_unicode_property(name)?;
+        Some(self.from_ranges(ranges))
+    }
+}
+
+/// 预定义的字符类:`\d`、`\w`、`\s`以及POSIX的`[:alpha:]`等
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PredefinedClass {
+    /// `\d`、`[:digit:]`
+    Digit,
+    /// `\w`、`[:word:]`
+    Word,
+    /// `\s`、`[:space:]`
+    Space,
+    /// `[:alpha:]`
+    Alpha,
+    /// `[:alnum:]`
+    Alnum,
+    /// `[:upper:]`
+    Upper,
+    /// `[:lower:]`
+    Lower,
+    /// `[:punct:]`
+    Punct,
+    /// `[:graph:]`
+    Graph,
+    /// `[:print:]`
+    Print,
+    /// `[:cntrl:]`
+    Cntrl,
+    /// `[:xdigit:]`
+    XDigit,
+    /// `[:blank:]`
+    Blank,
+    /// `[:ascii:]`
+    Ascii,
+}
+
+impl PredefinedClass {
+    /// 由POSIX字符类的名字(如`alpha`)得到预定义字符类
+    pub fn from_posix_name(name: &str) -> Option<PredefinedClass> {
+        let class = match name {
+            "digit" => PredefinedClass::Digit,
+            "word" => PredefinedClass::Word,
+            "space" => PredefinedClass::Space,
+            "alpha" => PredefinedClass::Alpha,
+            "alnum" => PredefinedClass::Alnum,
+            "upper" => PredefinedClass::Upper,
+            "lower" => PredefinedClass::Lower,
+            "punct" => PredefinedClass::Punct,
+            "graph" => PredefinedClass::Graph,
+            "print" => PredefinedClass::Print,
+            "cntrl" => PredefinedClass::Cntrl,
+            "xdigit" => PredefinedClass::XDigit,
+            "blank" => PredefinedClass::Blank,
+            "ascii" => PredefinedClass::Ascii,
+            _ => return None,
+        };
+        Some(class)
+    }
+    /// 字符类包含的字符范围;unicode为false时只取ASCII的定义,
+    /// 为true时按UTS #18附录C的定义取Unicode属性
+    pub fn ranges(self, unicode: bool) -> Vec<(char, char)> {
+        let mut ranges: Vec<(char, char)> = if unicode {
+            let property = |name: &str| -> Vec<(char, char)> {
+                lookup_unicode_property(name)
+                    .expect("unicode property table is incomplete")
+                    .to_vec()
+            };
+            match self {
+                PredefinedClass::Digit => property("Nd"),
+                PredefinedClass::Word => [
+                    property("Alphabetic"),
+                    property("M"),
+                    property("Nd"),
+                    property("Pc"),
+                    // Join_Control
+                    vec![('\u{200C}', '\u{200D}')],
+                ]
+                .concat(),
+                PredefinedClass::Space => property("White_Space"),
+                PredefinedClass::Alpha => property("Alphabetic"),
+                PredefinedClass::Alnum => [property("Alphabetic"), property("Nd")].concat(),
+                PredefinedClass::Upper => property("Lu"),
+                PredefinedClass::Lower => property("Ll"),
+                PredefinedClass::Punct => property("P"),
+                PredefinedClass::Graph => complement_ranges(
+                    &[property("White_Space"), property("Cc"), property("Cn")].concat(),
+                ),
+                PredefinedClass::Print => {
+                    let graph = PredefinedClass::Graph.ranges(true);
+                    let blank = PredefinedClass::Blank.ranges(true);
+                    subtract_ranges(&[graph, blank].concat(), &property("Cc"))
+                }
+                PredefinedClass::Cntrl => property("Cc"),
+                PredefinedClass::XDigit => vec![('0', '9'), ('A', 'F'), ('a', 'f')],
+                PredefinedClass::Blank => [property("Zs"), vec![('\t', '\t')]].concat(),
+                PredefinedClass::Ascii => vec![('\0', '\u{7F}')],
+            }
+        } else {
+            match self {
+                PredefinedClass::Digit => vec![('0', '9')],
+                PredefinedClass::Word => vec![('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')],
+                PredefinedClass::Space => vec![('\t', '\r'), (' ', ' ')],
+                PredefinedClass::Alpha => vec![('A', 'Z'), ('a', 'z')],
+                PredefinedClass::Alnum => vec![('0', '9'), ('A', 'Z'), ('a', 'z')],
+                PredefinedClass::Upper => vec![('A', 'Z')],
+                PredefinedClass::Lower => vec![('a', 'z')],
+                PredefinedClass::Punct => {
+                    vec![('!', '/'), (':', '@'), ('[', '`'), ('{', '~')]
+                }
+                PredefinedClass::Graph => vec![('!', '~')],
+                PredefinedClass::Print => vec![(' ', '~')],
+                PredefinedClass::Cntrl => vec![('\0', '\u{1F}'), ('\u{7F}', '\u{7F}')],
+                PredefinedClass::XDigit => vec![('0', '9'), ('A', 'F'), ('a', 'f')],
+                PredefinedClass::Blank => vec![('\t', '\t'), (' ', ' ')],
+                PredefinedClass::Ascii => vec![('\0', '\u{7F}')],
+            }
+        };
+        normalize_ranges(&mut ranges);
+        ranges
+    }
+}
+
+impl CharSetTable {
+    /// 预定义字符类,生成一个新的字符集加入到字符集表中
+    pub fn predefined_class(&mut self, class: PredefinedClass, unicode: bool) -> i32 {
+        self.from_ranges(&class.ranges(unicode))
+    }
+    /// 预定义字符类的补集,如`\D`、`[:^alpha:]`,生成一个新的字符集加入到字符集表中
+    pub fn negated_predefined_class(&mut self, class: PredefinedClass, unicode: bool) -> i32 {
+        self.from_ranges(&complement_ranges(&class.ranges(unicode)))
+    }
+    /// 字符集相对于全体字符的补集,生成一个新的字符集加入到字符集表中
+    pub fn complement(&mut self, index_id: i32) -> i32 {
+        let ranges = complement_ranges(&self.ranges(index_id));
+        self.from_ranges(&ranges)
+    }
+    /// 字符集的大小写折叠:加入与字符集中字符简单大小写折叠结果相同的所有字符,
+    /// 生成一个新的字符集加入到字符集表中
+    pub fn case_fold(&mut self, index_id: i32) -> i32 {
+        let ranges = case_fold_ranges(&self.ranges(index_id));
+        self.from_ranges(&ranges)
+    }
+}
+
+/// 字符范围相对于全体字符的补集,char无法表示的代理区自然被跳过
+pub(crate) fn complement_ranges(ranges: &[(char, char)]) -> Vec<(char, char)> {
+    let mut ranges = ranges.to_vec();
+    normalize_ranges(&mut ranges);
+    let mut result = Vec::new();
+    let mut next: u32 = 0;
+    for &(from_char, to_char) in ranges.iter() {
+        if (from_char as u32) > next {
+            push_code_range(&mut result, next, from_char as u32 - 1);
+        }
+        next = to_char as u32 + 1;
+    }
+    if next <= char::MAX as u32 {
+        push_code_range(&mut result, next, char::MAX as u32);
+    }
+    result
+}
+
+/// 两组字符范围的交集
+pub(crate) fn intersect_ranges(a: &[(char, char)], b: &[(char, char)]) -> Vec<(char, char)> {
+    let (mut a, mut b) = (a.to_vec(), b.to_vec());
+    normalize_ranges(&mut a);
+    normalize_ranges(&mut b);
+    let mut result = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let from_char = a[i].0.max(b[j].0);
+        let to_char = a[i].1.min(b[j].1);
+        if from_char <= to_char {
+            result.push((from_char, to_char));
+        }
+        if a[i].1 < b[j].1 {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    result
+}
+
+/// 字符范围的简单大小写折叠闭包:折叠结果相同的字符属于同一个等价类,
+/// 把范围中每个字符所在的等价类都加入结果
+pub(crate) fn case_fold_ranges(ranges: &[(char, char)]) -> Vec<(char, char)> {
+    let folding = unicode_tables::SIMPLE_CASE_FOLDING;
+    let contains = |c: char| ranges.iter().any(|&(from, to)| from <= c && c <= to);
+    // 范围中的字符折叠后的结果;没有折叠项的字符折叠为自身,只有作为其他字符的折叠结果时才需要
+    let mut folds: Vec<char> = Vec::new();
+    for &(c, fold) in folding.iter() {
+        if contains(c) || contains(fold) {
+            folds.push(fold);
+        }
+    }
+    folds.sort();
+    folds.dedup();
+    let mut result = ranges.to_vec();
+    for &(c, fold) in folding.iter() {
+        if folds.binary_search(&fold).is_ok() {
+            result.push((c, c));
+        }
+    }
+    result.extend(folds.iter().map(|&c| (c, c)));
+    normalize_ranges(&mut result);
+    result
+}
+
+/// 字符的简单大小写折叠,没有折叠项的字符折叠为自身
+pub(crate) fn simple_case_fold(c: char) -> char {
+    let folding = unicode_tables::SIMPLE_CASE_FOLDING;
+    match folding.binary_search_by_key(&c, |&(from, _)| from) {
+        Ok(index) => folding[index].1,
+        Err(_) => c,
+    }
+}
+
+/// 两组字符范围的差集a-b
+pub(crate) fn subtract_ranges(a: &[(char, char)], b: &[(char, char)]) -> Vec<(char, char)> {
+    intersect_ranges(a, &complement_ranges(b))
+}
+
+/// 把若干组字符范围加细为互不相交的等价类:同一个等价类中的字符属于相同的若干组,
+/// 等价类的并恰好是所有组的并
+pub(crate) fn partition_ranges(sets: &[Vec<(char, char)>]) -> Vec<Vec<(char, char)>> {
+    let mut classes: Vec<Vec<(char, char)>> = Vec::new();
+    let mut covered: Vec<(char, char)> = Vec::new();
+    for set in sets.iter() {
+        let mut refined = Vec::with_capacity(classes.len() + 1);
+        for class in classes.iter() {
+            for part in [intersect_ranges(class, set), subtract_ranges(class, set)] {
+                if !part.is_empty() {
+                    refined.push(part);
+                }
+            }
+        }
+        let rest = subtract_ranges(set, &covered);
+        if !rest.is_empty() {
+            refined.push(rest);
+        }
+        covered.extend_from_slice(set);
+        normalize_ranges(&mut covered);
+        classes = refined;
+    }
+    classes
+}
+
+/// 把码位范围[from, to]加入结果,跳过其中的代理区
+fn push_code_range(result: &mut Vec<(char, char)>, from: u32, to: u32) {
+    for (from, to) in [(from, to.min(0xD7FF)), (from.max(0xE000), to)] {
+        if from <= to {
+            if let (Some(from_char), Some(to_char)) = (char::from_u32(from), char::from_u32(to)) {
+                result.push((from_char, to_char));
+            }
+        }
+    }
+}
+
+/// 排序并合并相交或相邻的字符范围
+pub(crate) fn normalize_ranges(ranges: &mut Vec<(char, char)>) {
+    for range in ranges.iter_mut() {
+        if range.0 > range.1 {
+            swap(&mut range.0, &mut range.1);
+        }
+    }
+    ranges.sort();
+    let mut merged: Vec<(char, char)> = Vec::with_capacity(ranges.len());
+    for &(from_char, to_char) in ranges.iter() {
+        match merged.last_mut() {
+            Some(last) if from_char as u32 <= last.1 as u32 + 1 => {
+                if to_char > last.1 {
+                    last.1 = to_char;
+                }
+            }
+            _ => merged.push((from_char, to_char)),
+        }
+    }
+    *ranges = merged;
+}
+
+/// 按UAX #44的宽松匹配规则规范化属性名:忽略大小写、空格、`_`和`-`
+fn loose_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '_' | '-'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// 在生成的Unicode范围表中查找属性值
+fn lookup_value(
+    table: &[(&str, &'static [(char, char)])],
+    value: &str,
+) -> Option<&'static [(char, char)]> {
+    let value = loose_name(value);
+    // 全称先转换为简称
+    let short = unicode_tables::VALUE_ALIASES
+        .iter()
+        .find(|(long, _)| loose_name(long) == value)
+        .map(|&(_, short)| loose_name(short))
+        .unwrap_or(value);
+    table
+        .iter()
+        .find(|(name, _)| loose_name(name) == short)
+        .map(|&(_, ranges)| ranges)
+}
+
+/// 查找Unicode属性对应的字符范围
+pub(crate) fn lookup_unicode_property(name: &str) -> Option<&'static [(char, char)]> {
+    if let Some((property, value)) = name.split_once('=') {
+        return match loose_name(property).as_str() {
+            "gc" | "generalcategory" => lookup_value(unicode_tables::GENERAL_CATEGORY, value),
+            "sc" | "script" => lookup_value(unicode_tables::SCRIPT, value),
+            _ => None,
+        };
+    }
+    match loose_name(name).as_str() {
+        "whitespace" | "wspace" | "space" => Some(unicode_tables::WHITE_SPACE),
+        "alphabetic" | "alpha" => Some(unicode_tables::ALPHABETIC),
+        _ => lookup_value(unicode_tables::GENERAL_CATEGORY, name)
+            .or_else(|| lookup_value(unicode_tables::SCRIPT, name)),
+    }
+}
+
 //TODO
 /// 合并两个相同char_set_id,不同segment_id,无需合并则返回None
 fn merge_char_set(char_set1: &CharSet, char_set2: &CharSet) -> Option<CharSet> {
     //范围合并算法
     //先保证char_set1的最左值小于或等于char_set2的最左值
-    
+
     None
 }
 
@@ -430,7 +801,6 @@ mod tests {
             to_char: 'b',
         };
 
-
         p_char_set_table.push(char_set1);
         p_char_set_table.push(char_set2);
 
@@ -440,5 +810,81 @@ mod tests {
     #[test]
     fn test_merge_char_set() {}
 
-    
+    /// 字符范围的排序与合并
+    #[test]
+    fn test_ranges() {
+        let mut p_char_set_table = CharSetTable::new();
+        let id = p_char_set_table.from_ranges(&[('x', 'z'), ('a', 'c'), ('d', 'f'), ('b', 'b')]);
+        assert_eq!(p_char_set_table.ranges(id), vec![('a', 'f'), ('x', 'z')]);
+        assert!(p_char_set_table.contains_char(id, 'e'));
+        assert!(!p_char_set_table.contains_char(id, 'g'));
+    }
+
+    /// Unicode通用类别、文字以及White_Space、Alphabetic属性
+    #[test]
+    fn test_unicode_property() {
+        let mut p_char_set_table = CharSetTable::new();
+        let letter = p_char_set_table.unicode_property("L").unwrap();
+        assert!(p_char_set_table.contains_char(letter, 'a'));
+        assert!(p_char_set_table.contains_char(letter, '中'));
+        assert!(!p_char_set_table.contains_char(letter, '1'));
+        let digit = p_char_set_table.unicode_property("Decimal_Number").unwrap();
+        assert!(p_char_set_table.contains_char(digit, '٣'));
+        assert!(!p_char_set_table.contains_char(digit, 'Ⅻ'));
+        let upper = p_char_set_table
+            .unicode_property("gc = uppercase letter")
+            .unwrap();
+        assert!(p_char_set_table.contains_char(upper, 'Σ'));
+        assert!(!p_char_set_table.contains_char(upper, 'σ'));
+        let greek = p_char_set_table.unicode_property("Greek").unwrap();
+        assert!(p_char_set_table.contains_char(greek, 'λ'));
+        assert!(!p_char_set_table.contains_char(greek, 'l'));
+        let han = p_char_set_table.unicode_property("sc=Hani").unwrap();
+        assert!(p_char_set_table.contains_char(han, '字'));
+        let space = p_char_set_table.unicode_property("White_Space").unwrap();
+        assert!(p_char_set_table.contains_char(space, '\u{3000}'));
+        assert!(!p_char_set_table.contains_char(space, '\u{200B}'));
+        let alpha = p_char_set_table.unicode_property("Alphabetic").unwrap();
+        assert!(p_char_set_table.contains_char(alpha, 'Ⅻ'));
+        assert_eq!(p_char_set_table.unicode_property("NotAProperty"), None);
+        assert_eq!(p_char_set_table.unicode_property("gc=Greek"), None);
+    }
+
+    /// 字符集的大小写折叠
+    #[test]
+    fn test_case_fold() {
+        let mut p_char_set_table = CharSetTable::new();
+        let id = p_char_set_table.from_ranges(&[('a', 'c'), ('k', 'k'), ('Σ', 'Σ'), ('1', '1')]);
+        let folded = p_char_set_table.case_fold(id);
+        for c in ['a', 'B', 'c', 'k', 'K', '\u{212A}', 'σ', 'ς', 'Σ', '1'] {
+            assert!(p_char_set_table.contains_char(folded, c), "{}", c);
+        }
+        assert!(!p_char_set_table.contains_char(folded, 'D'));
+        // 开尔文符号K折叠为k,k所在的等价类包含k、K和K
+        let kelvin = p_char_set_table.from_ranges(&[('\u{212A}', '\u{212A}')]);
+        let folded = p_char_set_table.case_fold(kelvin);
+        assert_eq!(
+            p_char_set_table.ranges(folded),
+            vec![('K', 'K'), ('k', 'k'), ('\u{212A}', '\u{212A}')]
+        );
+    }
+
+    /// 字符范围加细为互不相交的等价类
+    #[test]
+    fn test_partition_ranges() {
+        let classes = partition_ranges(&[
+            vec![('a', 'z')],
+            vec![('x', 'x')],
+            vec![('0', '9'), ('a', 'f')],
+        ]);
+        assert_eq!(
+            classes,
+            vec![
+                vec![('x', 'x')],
+                vec![('a', 'f')],
+                vec![('g', 'w'), ('y', 'z')],
+                vec![('0', '9')],
+            ]
+        );
+    }
 }
@@ -0,0 +1,169 @@
+use super::char_set::CharSetTable;
+use super::graph::{Category, DriverType, Graph, StateType};
+
+impl<C: Category> Graph<C> {
+    /// 长度为0..=max_len的被接受的串各有多少个,结果在u128上饱和(超过u128::MAX时为u128::MAX)。
+    /// 先用子集构造法确定化,DFA中从同一状态出发的边上的字符互不相交,
+    /// 于是长度为n+1的计数由长度为n的计数乘以边上的字符个数累加得到
+    pub fn count_by_length(&self, max_len: usize, table: &mut CharSetTable) -> Vec<u128> {
+        let dfa = self.nfa_to_dfa(table);
+        let weights: Vec<u128> = dfa
+            .p_edge_table
+            .iter()
+            .map(|edge| match edge.driver_type {
+                DriverType::NULL => 0,
+                DriverType::CHAR => 1,
+                DriverType::CHARSET => table
+                    .ranges(edge.driver_id)
+                    .iter()
+                    .map(|&(from_char, to_char)| (to_char as u128) - (from_char as u128) + 1)
+                    .sum(),
+            })
+            .collect();
+        // ways[s]:读入长度为n的串后停在状态s的串的个数
+        let mut ways: Vec<u128> = vec![0; dfa.p_state_table.len()];
+        ways[0] = 1;
+        let mut result = Vec::with_capacity(max_len + 1);
+        for len in 0..=max_len {
+            let accepted = dfa
+                .p_state_table
+                .iter()
+                .filter(|state| state.state_type == StateType::MATCH)
+                .fold(0u128, |sum, state| {
+                    sum.saturating_add(ways[state.state_id as usize])
+                });
+            result.push(accepted);
+            if len == max_len {
+                break;
+            }
+            let mut next: Vec<u128> = vec![0; ways.len()];
+            for (edge, &weight) in dfa.p_edge_table.iter().zip(weights.iter()) {
+                let from = ways[edge.from_state as usize];
+                let to = &mut next[edge.next_state as usize];
+                *to = to.saturating_add(from.saturating_mul(weight));
+            }
+            ways = next;
+        }
+        result
+    }
+    /// 接受的串是否只有有限个:在既能从开始状态到达、又能到达结束状态的DFA状态中没有环
+    pub fn is_finite(&self, table: &mut CharSetTable) -> bool {
+        let dfa = self.nfa_to_dfa(table);
+        let len = dfa.p_state_table.len();
+        let mut reachable = vec![false; len];
+        reachable[0] = true;
+        let mut stack = vec![0usize];
+        while let Some(state) = stack.pop() {
+            for edge in dfa.p_edge_table.iter() {
+                if edge.from_state as usize == state && !reachable[edge.next_state as usize] {
+                    reachable[edge.next_state as usize] = true;
+                    stack.push(edge.next_state as usize);
+                }
+            }
+        }
+        let mut live: Vec<bool> = dfa
+            .p_state_table
+            .iter()
+            .map(|state| state.state_type == StateType::MATCH)
+            .collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for edge in dfa.p_edge_table.iter() {
+                if live[edge.next_state as usize] && !live[edge.from_state as usize] {
+                    live[edge.from_state as usize] = true;
+                    changed = true;
+                }
+            }
+        }
+        let useful: Vec<bool> = (0..len)
+            .map(|state| reachable[state] && live[state])
+            .collect();
+        // 在有用的状态上深度优先搜索找环:0未访问,1在栈上,2已完成
+        let mut color = vec![0u8; len];
+        for root in (0..len).filter(|&state| useful[state]) {
+            if color[root] != 0 {
+                continue;
+            }
+            // (状态, 下一条要检查的边的序号)
+            let mut stack: Vec<(usize, usize)> = vec![(root, 0)];
+            color[root] = 1;
+            while let Some(&mut (state, ref mut index)) = stack.last_mut() {
+                let edge = dfa.p_edge_table[*index..]
+                    .iter()
+                    .position(|edge| {
+                        edge.from_state as usize == state && useful[edge.next_state as usize]
+                    })
+                    .map(|offset| *index + offset);
+                match edge {
+                    Some(position) => {
+                        *index = position + 1;
+                        let next = dfa.p_edge_table[position].next_state as usize;
+                        match color[next] {
+                            1 => return false,
+                            0 => {
+                                color[next] = 1;
+                                stack.push((next, 0));
+                            }
+                            _ => {}
+                        }
+                    }
+                    None => {
+                        color[state] = 2;
+                        stack.pop();
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::compile_with_set_operations;
+
+    fn count(pattern: &str, max_len: usize) -> Vec<u128> {
+        let mut table = CharSetTable::new();
+        compile_with_set_operations(pattern, &mut table)
+            .unwrap()
+            .count_by_length(max_len, &mut table)
+    }
+
+    /// 各长度的串的个数
+    #[test]
+    fn test_count_by_length() {
+        assert_eq!(count("(a|b)*", 4), vec![1, 2, 4, 8, 16]);
+        assert_eq!(count("[a-z][a-z0-9]*", 2), vec![0, 26, 26 * 36]);
+        // 有歧义的NFA中同一个串只计一次
+        assert_eq!(count("a*a*|aa", 3), vec![1, 1, 1, 1]);
+        assert_eq!(count("a{2,3}|b", 4), vec![0, 1, 1, 1, 0]);
+        assert_eq!(count("a&b", 2), vec![0, 0, 0]);
+        // 任意字符构成的串的个数很快超过u128::MAX而饱和
+        let any = count("[\\s\\S]*", 8);
+        let chars = 0x110000u128 - 0x800;
+        assert_eq!(any[1], chars);
+        assert_eq!(any[6], chars.pow(6));
+        assert_eq!(any[7], u128::MAX);
+    }
+
+    /// 有限与无限
+    #[test]
+    fn test_is_finite() {
+        let mut table = CharSetTable::new();
+        let cases = [
+            ("if|else|while", true),
+            ("[0-9]{1,3}", true),
+            ("a*", false),
+            ("a*&b", true),
+            ("(ab)+c", false),
+            ("x(a*&~a*)", true),
+            ("", true),
+        ];
+        for (pattern, expected) in cases {
+            let graph = compile_with_set_operations(pattern, &mut table).unwrap();
+            assert_eq!(graph.is_finite(&mut table), expected, "{}", pattern);
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use std::fmt::Display;
+
+use super::graph::{Category, LexemeCategory};
+use super::tokenizer::Token;
+
+/// 由词素解码得到的值
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenValue {
+    /// 不带u后缀的整数
+    Int(i64),
+    /// 带u后缀的整数
+    UInt(u64),
+    Float(f64),
+    /// 去掉引号并处理转义后的字符串
+    Str(String),
+}
+
+/// 解码错误,位置是相对于词素开头的字节偏移
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodeError {
+    /// 出错部分在词素中的字节偏移
+    pub offset: usize,
+    /// 出错部分的字节长度
+    pub len: usize,
+    pub message: String,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at byte {} of literal", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+fn error(offset: usize, len: usize, message: &str) -> DecodeError {
+    DecodeError {
+        offset,
+        len,
+        message: message.to_string(),
+    }
+}
+
+/// 解码C风格的整数:`0x`/`0X`开头为十六进制,`0b`/`0B`开头为二进制,其他以0开头的为八进制;
+/// 后缀u、l、ll不区分大小写,带u时为UInt,否则为Int,超出范围时出错
+pub fn decode_integer(text: &str) -> Result<TokenValue, DecodeError> {
+    let number = text.trim_end_matches(['u', 'U', 'l', 'L']);
+    let unsigned = text[number.len()..].contains(['u', 'U']);
+    let (radix, start) = match number.as_bytes() {
+        [b'0', b'x' | b'X', ..] => (16, 2),
+        [b'0', b'b' | b'B', ..] => (2, 2),
+        [b'0', _, ..] => (8, 1),
+        _ => (10, 0),
+    };
+    if number.len() == start {
+        return Err(error(0, text.len(), "missing digits in integer literal"));
+    }
+    let mut value: u64 = 0;
+    for (offset, c) in number.char_indices().skip(start) {
+        let digit = match c.to_digit(radix) {
+            Some(digit) => digit as u64,
+            None => {
+                return Err(error(
+                    offset,
+                    c.len_utf8(),
+                    "invalid digit in integer literal",
+                ))
+            }
+        };
+        value = match value
+            .checked_mul(radix as u64)
+            .and_then(|value| value.checked_add(digit))
+        {
+            Some(value) => value,
+            None => return Err(error(0, number.len(), "integer literal too large for u64")),
+        };
+    }
+    if unsigned {
+        Ok(TokenValue::UInt(value))
+    } else {
+        match i64::try_from(value) {
+            Ok(value) => Ok(TokenValue::Int(value)),
+            Err(_) => Err(error(0, number.len(), "integer literal too large for i64")),
+        }
+    }
+}
+
+/// 解码实数与科学计数法,去掉结尾的f或l后缀;结果超出f64的范围时出错
+pub fn decode_float(text: &str) -> Result<TokenValue, DecodeError> {
+    let number = text.strip_suffix(['f', 'F', 'l', 'L']).unwrap_or(text);
+    match number.parse::<f64>() {
+        Ok(value) if value.is_finite() => Ok(TokenValue::Float(value)),
+        Ok(_) => Err(error(0, text.len(), "float literal out of range")),
+        Err(_) => Err(error(0, text.len(), "invalid float literal")),
+    }
+}
+
+/// 解码带双引号的字符串常量,支持简单转义、八进制转义`\0`~`\377`、`\x..`、`\u....`与`\U........`,
+/// 八进制转义按C的规则最多取三位数字,超过`\377`或转义得到的码位不是合法的字符时出错,
+/// 错误位置指向该转义序列
+pub fn decode_string(text: &str) -> Result<TokenValue, DecodeError> {
+    let body = match text
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+    {
+        Some(body) if text.len() >= 2 => body,
+        _ => return Err(error(0, text.len(), "string literal must be quoted")),
+    };
+    let mut value = String::with_capacity(body.len());
+    let mut chars = body.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        // 转义序列在词素中的开始位置(算上开头的引号)
+        let offset = index + 1;
+        let escape = match chars.next() {
+            Some((_, escape)) => escape,
+            None => return Err(error(offset, 1, "unterminated escape sequence")),
+        };
+        let simple = match escape {
+            'a' => Some('\u{7}'),
+            'b' => Some('\u{8}'),
+            'f' => Some('\u{C}'),
+            'n' => Some('\n'),
+            'r' => Some('\r'),
+            't' => Some('\t'),
+            'v' => Some('\u{B}'),
+            '\\' | '\'' | '"' | '?' => Some(escape),
+            _ => None,
+        };
+        if let Some(simple) = simple {
+            value.push(simple);
+            continue;
+        }
+        // (进制, 最少位数, 最多位数, 已读的数字),八进制转义的第一位就是escape
+        let (radix, min, max, digits) = match escape {
+            '0'..='7' => (8, 1, 3, escape.to_string()),
+            'x' => (16, 1, usize::MAX, String::new()),
+            'u' => (16, 4, 4, String::new()),
+            'U' => (16, 8, 8, String::new()),
+            _ => {
+                return Err(error(
+                    offset,
+                    1 + escape.len_utf8(),
+                    "unknown escape sequence",
+                ))
+            }
+        };
+        let mut digits = digits;
+        while digits.len() < max {
+            match chars.peek() {
+                Some(&(_, digit)) if digit.is_digit(radix) => {
+                    digits.push(digit);
+                    chars.next();
+                }
+                _ => break,
+            }
+        }
+        let len = 2 + digits.len() - usize::from(radix == 8);
+        if digits.len() < min {
+            return Err(error(offset, len, "too few digits in escape sequence"));
+        }
+        let code = u32::from_str_radix(&digits, radix).ok();
+        if radix == 8 && code.is_some_and(|code| code > 0o377) {
+            return Err(error(offset, len, "octal escape sequence out of range"));
+        }
+        match code.and_then(char::from_u32) {
+            Some(c) => value.push(c),
+            None => {
+                return Err(error(
+                    offset,
+                    len,
+                    "escape sequence is not a valid character",
+                ))
+            }
+        }
+    }
+    Ok(TokenValue::Str(value))
+}
+
+/// 解码函数
+pub type Decoder = fn(&str) -> Result<TokenValue, DecodeError>;
+
+/// 种别 -> 解码函数,词法分析时对这些种别的单词解码
+#[derive(Clone, Debug)]
+pub struct Decoders<C = LexemeCategory> {
+    decoders: Vec<(C, Decoder)>,
+}
+
+impl<C> Default for Decoders<C> {
+    fn default() -> Self {
+        Decoders {
+            decoders: Vec::new(),
+        }
+    }
+}
+
+impl Decoders {
+    /// INTEGER_CONST、FLOAT_CONST、SCIENTIFIC_CONST与STRING_CONST的解码函数
+    pub fn standard() -> Decoders {
+        let mut decoders = Decoders::new();
+        decoders.add(LexemeCategory::INTEGER_CONST, decode_integer);
+        decoders.add(LexemeCategory::FLOAT_CONST, decode_float);
+        decoders.add(LexemeCategory::SCIENTIFIC_CONST, decode_float);
+        decoders.add(LexemeCategory::STRING_CONST, decode_string);
+        decoders
+    }
+}
+
+impl<C: Category> Decoders<C> {
+    pub fn new() -> Decoders<C> {
+        Decoders::default()
+    }
+    /// 设置种别category的解码函数,已有时替换
+    pub fn add(&mut self, category: C, decoder: Decoder) {
+        match self.decoders.iter_mut().find(|(item, _)| *item == category) {
+            Some((_, item)) => *item = decoder,
+            None => self.decoders.push((category, decoder)),
+        }
+    }
+    /// 对单词解码,结果放在token.value中;该种别没有解码函数时不改变单词
+    pub fn decode(&self, token: &mut Token<C>) -> Result<(), DecodeError> {
+        if let Some((_, decoder)) = self
+            .decoders
+            .iter()
+            .find(|(item, _)| *item == token.category)
+        {
+            token.value = Some(decoder(&token.text)?);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 整数的进制、后缀与溢出
+    #[test]
+    fn test_decode_integer() {
+        let cases = [
+            ("0", TokenValue::Int(0)),
+            ("42", TokenValue::Int(42)),
+            ("0x1F", TokenValue::Int(31)),
+            ("0755", TokenValue::Int(493)),
+            ("0b101", TokenValue::Int(5)),
+            ("7ULL", TokenValue::UInt(7)),
+            ("9223372036854775807", TokenValue::Int(i64::MAX)),
+            ("18446744073709551615u", TokenValue::UInt(u64::MAX)),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(decode_integer(text), Ok(expected), "{}", text);
+        }
+        let message = |text: &str| decode_integer(text).unwrap_err().message;
+        assert_eq!(
+            message("9223372036854775808"),
+            "integer literal too large for i64"
+        );
+        assert_eq!(
+            message("18446744073709551616u"),
+            "integer literal too large for u64"
+        );
+        assert_eq!(message("0x"), "missing digits in integer literal");
+        assert_eq!(decode_integer("0789").unwrap_err().offset, 2);
+    }
+
+    /// 实数与科学计数法
+    #[test]
+    fn test_decode_float() {
+        assert_eq!(decode_float("2.75"), Ok(TokenValue::Float(2.75)));
+        assert_eq!(decode_float(".5f"), Ok(TokenValue::Float(0.5)));
+        assert_eq!(decode_float("1.e3L"), Ok(TokenValue::Float(1000.0)));
+        assert_eq!(decode_float("6.02E+23"), Ok(TokenValue::Float(6.02e23)));
+        assert_eq!(
+            decode_float("1e400").unwrap_err().message,
+            "float literal out of range"
+        );
+    }
+
+    /// 转义与错误位置
+    #[test]
+    fn test_decode_string() {
+        let decoded = |text: &str| match decode_string(text) {
+            Ok(TokenValue::Str(value)) => value,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(decoded(r#""""#), "");
+        assert_eq!(decoded(r#""a\tb\n""#), "a\tb\n");
+        assert_eq!(decoded(r#""\"\\\?\'""#), "\"\\?'");
+        assert_eq!(decoded(r#""\101\0\x41中\U0001F600""#), "A\0A中😀");
+        assert_eq!(decoded(r#""\1234""#), "S4");
+        assert_eq!(decoded("\"中\\x6587\""), "中\u{6587}");
+        let failed = |text: &str| {
+            let error = decode_string(text).unwrap_err();
+            (error.offset, error.len, error.message)
+        };
+        assert_eq!(
+            failed(r#""ab\q""#),
+            (3, 2, "unknown escape sequence".to_string())
+        );
+        assert_eq!(
+            failed(r#""\u12x""#),
+            (1, 4, "too few digits in escape sequence".to_string())
+        );
+        assert_eq!(
+            failed(r#""a\777""#),
+            (2, 4, "octal escape sequence out of range".to_string())
+        );
+        assert_eq!(decoded(r#""\3777""#), "\u{FF}7");
+        assert_eq!(
+            failed(r#""\uD800""#),
+            (1, 6, "escape sequence is not a valid character".to_string())
+        );
+        assert_eq!(failed("abc").2, "string literal must be quoted");
+        assert_eq!(failed("\"").2, "string literal must be quoted");
+    }
+}
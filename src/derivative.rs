@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+
+use super::ast::{AssertionKind, Ast};
+use super::char_set::{complement_ranges, intersect_ranges, normalize_ranges, CharSetTable};
+use super::graph::{Graph, LexemeCategory, State, StateType};
+use super::pattern::PatternError;
+
+/// 导数构造法使用的正则表达式,由构造函数规范化:选择与交的各项排序去重,
+/// 连接右结合,使得相似的表达式相等,从而一个表达式只有有限个不同的导数
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Term {
+    /// 空语言∅
+    Nothing,
+    /// 空串ε
+    Epsilon,
+    /// 非空的字符类
+    Set(Vec<(char, char)>),
+    /// 连接,左项不是连接
+    Concat(Box<Term>, Box<Term>),
+    /// 闭包
+    Star(Box<Term>),
+    /// 选择,至少两项,已排序去重
+    Or(Vec<Term>),
+    /// 交,至少两项,已排序去重
+    And(Vec<Term>),
+    /// 补
+    Not(Box<Term>),
+}
+
+impl Term {
+    /// 全体串的语言,即∅的补
+    fn universal() -> Term {
+        Term::Not(Box::new(Term::Nothing))
+    }
+    fn set(ranges: &[(char, char)]) -> Term {
+        let mut ranges = ranges.to_vec();
+        normalize_ranges(&mut ranges);
+        if ranges.is_empty() {
+            Term::Nothing
+        } else {
+            Term::Set(ranges)
+        }
+    }
+    fn concat(left: Term, right: Term) -> Term {
+        match (left, right) {
+            (Term::Nothing, _) | (_, Term::Nothing) => Term::Nothing,
+            (Term::Epsilon, term) | (term, Term::Epsilon) => term,
+            (Term::Concat(first, rest), right) => Term::concat(*first, Term::concat(*rest, right)),
+            (left, right) => Term::Concat(Box::new(left), Box::new(right)),
+        }
+    }
+    fn star(term: Term) -> Term {
+        match term {
+            Term::Nothing | Term::Epsilon => Term::Epsilon,
+            Term::Star(_) => term,
+            term => Term::Star(Box::new(term)),
+        }
+    }
+    fn not(term: Term) -> Term {
+        match term {
+            Term::Not(term) => *term,
+            term => Term::Not(Box::new(term)),
+        }
+    }
+    /// 选择:展开内层的选择,所有字符类(包括内层选择中的)合并为一个
+    fn or(items: Vec<Term>) -> Term {
+        let mut result = Vec::new();
+        let mut ranges: Vec<(char, char)> = Vec::new();
+        let mut items = items;
+        while let Some(item) = items.pop() {
+            match item {
+                Term::Nothing => {}
+                Term::Or(inner) => items.extend(inner),
+                Term::Set(set) => ranges.extend(set),
+                item => result.push(item),
+            }
+        }
+        if result.contains(&Term::universal()) {
+            return Term::universal();
+        }
+        if !ranges.is_empty() {
+            result.push(Term::set(&ranges));
+        }
+        result.sort();
+        result.dedup();
+        match result.len() {
+            0 => Term::Nothing,
+            1 => result.pop().unwrap(),
+            _ => Term::Or(result),
+        }
+    }
+    /// 交:展开内层的交,所有字符类(包括内层交中的)相交为一个
+    fn and(items: Vec<Term>) -> Term {
+        let mut result = Vec::new();
+        let mut ranges: Option<Vec<(char, char)>> = None;
+        let mut items = items;
+        while let Some(item) = items.pop() {
+            match item {
+                Term::Nothing => return Term::Nothing,
+                Term::And(inner) => items.extend(inner),
+                Term::Set(set) => {
+                    ranges = Some(match ranges {
+                        Some(ranges) => intersect_ranges(&ranges, &set),
+                        None => set,
+                    })
+                }
+                item => result.push(item),
+            }
+        }
+        if let Some(ranges) = ranges {
+            match Term::set(&ranges) {
+                Term::Nothing => return Term::Nothing,
+                set => result.push(set),
+            }
+        }
+        result.retain(|item| *item != Term::universal());
+        result.sort();
+        result.dedup();
+        match result.len() {
+            0 => Term::universal(),
+            1 => result.pop().unwrap(),
+            _ => Term::And(result),
+        }
+    }
+
+    /// 由语法树构造,单词边界断言无法构造,返回position为0的错误
+    fn from_ast(ast: &Ast) -> Result<Term, PatternError> {
+        let all = |items: &[Ast]| items.iter().map(Term::from_ast).collect::<Result<_, _>>();
+        Ok(match ast {
+            Ast::Empty => Term::Epsilon,
+            Ast::Literal(c) => Term::Set(vec![(*c, *c)]),
+            Ast::Class(ranges) => Term::set(ranges),
+            Ast::Concat(items) => {
+                let items: Vec<Term> = all(items)?;
+                items
+                    .into_iter()
+                    .rev()
+                    .fold(Term::Epsilon, |rest, item| Term::concat(item, rest))
+            }
+            Ast::Alternation(items) => Term::or(all(items)?),
+            Ast::Intersection(items) => Term::and(all(items)?),
+            Ast::Complement(ast) => Term::not(Term::from_ast(ast)?),
+            Ast::Group(ast) => Term::from_ast(ast)?,
+            // x{m,n}展开为m个x与n-m个x?的连接,x{m,}展开为m个x与x*的连接
+            Ast::Repeat { ast, min, max } => {
+                let term = Term::from_ast(ast)?;
+                let tail = match max {
+                    None => Term::star(term.clone()),
+                    Some(max) => (*min..*max).fold(Term::Epsilon, |rest, _| {
+                        Term::concat(Term::or(vec![Term::Epsilon, term.clone()]), rest)
+                    }),
+                };
+                (0..*min).fold(tail, |rest, _| Term::concat(term.clone(), rest))
+            }
+            Ast::Assertion(AssertionKind::StartText | AssertionKind::EndText) => Term::Epsilon,
+            Ast::Assertion(_) => {
+                return Err(PatternError {
+                    position: 0,
+                    message: format!("assertion {} cannot be compiled into a DFA", ast),
+                })
+            }
+        })
+    }
+
+    /// 是否接受空串
+    fn nullable(&self) -> bool {
+        match self {
+            Term::Nothing | Term::Set(_) => false,
+            Term::Epsilon | Term::Star(_) => true,
+            Term::Concat(left, right) => left.nullable() && right.nullable(),
+            Term::Or(items) => items.iter().any(Term::nullable),
+            Term::And(items) => items.iter().all(Term::nullable),
+            Term::Not(term) => !term.nullable(),
+        }
+    }
+    /// 关于字符c的Brzozowski导数:{w | cw被self接受}
+    fn derivative(&self, c: char) -> Term {
+        match self {
+            Term::Nothing | Term::Epsilon => Term::Nothing,
+            Term::Set(ranges) => {
+                if ranges.iter().any(|&(from, to)| from <= c && c <= to) {
+                    Term::Epsilon
+                } else {
+                    Term::Nothing
+                }
+            }
+            Term::Concat(left, right) => {
+                let first = Term::concat(left.derivative(c), (**right).clone());
+                if left.nullable() {
+                    Term::or(vec![first, right.derivative(c)])
+                } else {
+                    first
+                }
+            }
+            Term::Star(term) => Term::concat(term.derivative(c), self.clone()),
+            Term::Or(items) => Term::or(items.iter().map(|item| item.derivative(c)).collect()),
+            Term::And(items) => Term::and(items.iter().map(|item| item.derivative(c)).collect()),
+            Term::Not(term) => Term::not(term.derivative(c)),
+        }
+    }
+    /// 把全体字符划分为若干等价类,同一等价类中的字符的导数相同
+    fn classes(&self) -> Vec<Vec<(char, char)>> {
+        match self {
+            Term::Nothing | Term::Epsilon => vec![complement_ranges(&[])],
+            Term::Set(ranges) => [ranges.clone(), complement_ranges(ranges)]
+                .into_iter()
+                .filter(|class| !class.is_empty())
+                .collect(),
+            Term::Concat(left, right) => {
+                if left.nullable() {
+                    meet(&left.classes(), &right.classes())
+                } else {
+                    left.classes()
+                }
+            }
+            Term::Star(term) | Term::Not(term) => term.classes(),
+            Term::Or(items) | Term::And(items) => items
+                .iter()
+                .map(Term::classes)
+                .reduce(|classes, item| meet(&classes, &item))
+                .unwrap(),
+        }
+    }
+}
+
+/// 两个划分的交:两两相交得到的非空等价类
+fn meet(a: &[Vec<(char, char)>], b: &[Vec<(char, char)>]) -> Vec<Vec<(char, char)>> {
+    let mut result = Vec::new();
+    for x in a.iter() {
+        for y in b.iter() {
+            let class = intersect_ranges(x, y);
+            if !class.is_empty() {
+                result.push(class);
+            }
+        }
+    }
+    result
+}
+
+impl Ast {
+    /// 用**Brzozowski导数**直接构造DFA:每个状态是一个(规范化的)正则表达式,
+    /// 状态经过字符c到达其关于c的导数,接受空串的状态为结束状态。
+    /// 输入符号是使导数相同的字符等价类,到达同一状态的等价类合并为一条边,
+    /// 只含一个字符的生成CHAR边,否则生成CHARSET边并把字符集加入到table中。
+    /// 支持交与补,得到的DFA接近最小;不生成空语言∅对应的死状态。
+    /// 含有单词边界断言时返回错误
+    pub fn to_dfa(&self, table: &mut CharSetTable) -> Result<Graph, PatternError> {
+        let mut terms = vec![Term::from_ast(self)?];
+        let mut term_map: HashMap<Term, i32> = HashMap::new();
+        term_map.insert(terms[0].clone(), 0);
+        let mut dfa = Graph {
+            graph_id: 0,
+            num_of_states: 0,
+            p_edge_table: Vec::new(),
+            p_state_table: Vec::new(),
+        };
+        let mut from_state = 0;
+        while (from_state as usize) < terms.len() {
+            let term = terms[from_state as usize].clone();
+            // 目标状态 -> 到达它的字符
+            let mut targets: Vec<(i32, Vec<(char, char)>)> = Vec::new();
+            for class in term.classes() {
+                let next = term.derivative(class[0].0);
+                if next == Term::Nothing {
+                    continue;
+                }
+                let next_state = *term_map.entry(next.clone()).or_insert_with(|| {
+                    terms.push(next);
+                    terms.len() as i32 - 1
+                });
+                match targets.iter_mut().find(|(state, _)| *state == next_state) {
+                    Some((_, ranges)) => ranges.extend(class),
+                    None => targets.push((next_state, class)),
+                }
+            }
+            for (next_state, ranges) in targets {
+                dfa.add_ranges_edge(from_state, next_state, &ranges, table);
+            }
+            dfa.p_state_table.push(State {
+                state_id: from_state,
+                state_type: if term.nullable() {
+                    StateType::MATCH
+                } else {
+                    StateType::UNMATCH
+                },
+                category: LexemeCategory::EMPTY,
+            });
+            from_state += 1;
+        }
+        dfa.num_of_states = dfa.p_state_table.len() as i32;
+        Ok(dfa)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::{parse, PatternOptions};
+    use crate::test_util::all_strings;
+
+    fn parse_ast(pattern: &str) -> Ast {
+        parse(pattern, &PatternOptions::default()).unwrap()
+    }
+
+    /// 导数构造法与子集构造法得到的DFA接受相同的串,且状态数不多于后者
+    #[test]
+    fn test_equivalent_to_subset_construction() {
+        let patterns = [
+            "(a|b)*abb",
+            "a+b?c*",
+            "(ab|a)(bc|c)",
+            "[a-c]*c[^b]",
+            "a{2,3}|b{2,}",
+            "(a|ab)*b",
+            "",
+            "a[^\\s\\S]*|b[^\\s\\S]",
+        ];
+        let inputs = all_strings(&['a', 'b', 'c', 'd'], 5);
+        for pattern in patterns {
+            let mut table = CharSetTable::new();
+            let ast = parse_ast(pattern).simplify();
+            let dfa = ast.to_dfa(&mut table).unwrap();
+            let subset = ast.to_graph(&mut table).unwrap().nfa_to_dfa(&mut table);
+            assert!(dfa.num_of_states <= subset.num_of_states, "{}", pattern);
+            for input in inputs.iter() {
+                assert_eq!(
+                    dfa.simulate(input, &table),
+                    subset.simulate(input, &table),
+                    "{} on {:?}",
+                    pattern,
+                    input
+                );
+            }
+        }
+        // (a|b)*abb的最小DFA有4个状态
+        let mut table = CharSetTable::new();
+        assert_eq!(
+            parse_ast("(a|b)*abb")
+                .to_dfa(&mut table)
+                .unwrap()
+                .num_of_states,
+            4
+        );
+    }
+
+    /// 交与补
+    #[test]
+    fn test_intersection_and_complement() {
+        let mut table = CharSetTable::new();
+        // 以a开头且以b结尾
+        let both = Ast::Intersection(vec![parse_ast("a.*"), parse_ast(".*b")]);
+        let dfa = both.to_dfa(&mut table).unwrap();
+        for (input, expected) in [("ab", true), ("acb", true), ("a", false), ("ba", false)] {
+            assert_eq!(dfa.simulate(input, &table), expected, "{}", input);
+        }
+        // 不是关键字if的标识符
+        let id = Ast::Intersection(vec![
+            parse_ast("[a-z]+"),
+            Ast::Complement(Box::new(parse_ast("if"))),
+        ]);
+        let dfa = id.to_dfa(&mut table).unwrap();
+        for (input, expected) in [
+            ("i", true),
+            ("if", false),
+            ("iff", true),
+            ("x", true),
+            ("", false),
+        ] {
+            assert_eq!(dfa.simulate(input, &table), expected, "{}", input);
+        }
+        // 全体字符的字符类
+        let dfa = Ast::Complement(Box::new(parse_ast("[\\s\\S]b")))
+            .to_dfa(&mut table)
+            .unwrap();
+        assert!(dfa.simulate("中", &table));
+        assert!(!dfa.simulate("中b", &table));
+        // 补的补
+        let dfa = Ast::Complement(Box::new(Ast::Complement(Box::new(parse_ast("ab")))))
+            .to_dfa(&mut table)
+            .unwrap();
+        assert!(dfa.simulate("ab", &table));
+        assert!(!dfa.simulate("abc", &table));
+    }
+
+    /// 内层选择(交)中的字符类与外层的合并,两种结合方式得到相同的项与相同的状态数
+    #[test]
+    fn test_associativity() {
+        let (ab, b, c) = (parse_ast("ab"), parse_ast("b"), parse_ast("c"));
+        let left = Ast::Concat(vec![
+            parse_ast("ba|a?"),
+            Ast::Alternation(vec![
+                Ast::Alternation(vec![ab.clone(), c.clone()]),
+                b.clone(),
+            ]),
+        ]);
+        let right = Ast::Concat(vec![
+            parse_ast("ba|a?"),
+            Ast::Alternation(vec![ab, Ast::Alternation(vec![c, b])]),
+        ]);
+        let mut table = CharSetTable::new();
+        assert_eq!(
+            left.to_dfa(&mut table).unwrap().num_of_states,
+            right.to_dfa(&mut table).unwrap().num_of_states
+        );
+        // 与所有字符类都在同一层时的结果相同
+        let star = Term::star(Term::set(&[('a', 'a')]));
+        let (letters, hex) = (Term::set(&[('a', 'z')]), Term::set(&[('a', 'f')]));
+        let pair = |x: &Term, y: &Term| vec![x.clone(), y.clone()];
+        let flat = vec![letters.clone(), star.clone(), hex.clone()];
+        assert_eq!(
+            Term::or(vec![Term::or(pair(&letters, &star)), hex.clone()]),
+            Term::or(flat.clone())
+        );
+        assert_eq!(
+            Term::or(vec![letters.clone(), Term::or(pair(&star, &hex))]),
+            Term::or(flat.clone())
+        );
+        assert_eq!(
+            Term::and(vec![Term::and(pair(&letters, &star)), hex.clone()]),
+            Term::and(flat.clone())
+        );
+        assert_eq!(
+            Term::and(vec![letters, Term::and(pair(&star, &hex))]),
+            Term::and(flat)
+        );
+    }
+
+    /// 单词边界断言返回错误而不是panic
+    #[test]
+    fn test_assertion() {
+        let mut table = CharSetTable::new();
+        let error = parse_ast(r"\Ba").to_dfa(&mut table).unwrap_err();
+        assert_eq!(error.message, r"assertion \B cannot be compiled into a DFA");
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::char_set::{partition_ranges, CharSetTable};
+use super::graph::{Category, Graph, StateType};
+
+/// 乘积状态,None表示该边处于死状态
+type ProductState = (Option<i32>, Option<i32>);
+
+/// 对自动机的语言的判断的结果
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// 判断成立
+    Holds,
+    /// 判断不成立,附带最短的反例
+    Counterexample(String),
+}
+
+impl Verdict {
+    /// 判断是否成立
+    pub fn holds(&self) -> bool {
+        *self == Verdict::Holds
+    }
+}
+
+impl From<Option<String>> for Verdict {
+    fn from(counterexample: Option<String>) -> Self {
+        match counterexample {
+            Some(counterexample) => Verdict::Counterexample(counterexample),
+            None => Verdict::Holds,
+        }
+    }
+}
+
+impl<C: Category> Graph<C> {
+    /// 语言是否为空,不为空时反例是最短的被接受的串
+    pub fn is_empty(&self, table: &mut CharSetTable) -> Verdict {
+        self.shortest_witness(table).into()
+    }
+    /// 两个自动机是否接受相同的串,反例被其中一个接受而不被另一个接受
+    pub fn is_equivalent(&self, graph: &Graph<C>, table: &mut CharSetTable) -> Verdict {
+        self.find_difference(graph, table, |left, right| left != right)
+            .into()
+    }
+    /// self接受的串是否都被graph接受,反例被self接受而不被graph接受
+    pub fn is_subset_of(&self, graph: &Graph<C>, table: &mut CharSetTable) -> Verdict {
+        self.find_difference(graph, table, |left, right| left && !right)
+            .into()
+    }
+
+    /// 在两个DFA的乘积上广度优先搜索,找到最短的串,使两边的接受情况满足differs。
+    /// 一边没有转换时该边处于死状态(None),两边都处于死状态的乘积状态不再展开
+    fn find_difference(
+        &self,
+        graph: &Graph<C>,
+        table: &mut CharSetTable,
+        differs: impl Fn(bool, bool) -> bool,
+    ) -> Option<String> {
+        let (left, right) = (self.nfa_to_dfa(table), graph.nfa_to_dfa(table));
+        let mut sets = left.edge_ranges(table);
+        sets.extend(right.edge_ranges(table));
+        // 按最小的字符排序,使反例是最短的串中字典序最小的
+        let mut classes = partition_ranges(&sets);
+        classes.sort();
+        let is_match = |dfa: &Graph<C>, state: Option<i32>| {
+            state.is_some_and(|state| {
+                dfa.p_state_table[state as usize].state_type == StateType::MATCH
+            })
+        };
+        let mut visited: HashSet<ProductState> = HashSet::new();
+        let mut queue: VecDeque<(ProductState, String)> = VecDeque::new();
+        visited.insert((Some(0), Some(0)));
+        queue.push_back(((Some(0), Some(0)), String::new()));
+        while let Some(((p, q), input)) = queue.pop_front() {
+            if differs(is_match(&left, p), is_match(&right, q)) {
+                return Some(input);
+            }
+            for class in classes.iter() {
+                let c = class[0].0;
+                let next = (
+                    p.and_then(|p| left.dfa_next(p, c, table)),
+                    q.and_then(|q| right.dfa_next(q, c, table)),
+                );
+                if next != (None, None) && visited.insert(next) {
+                    queue.push_back((next, format!("{}{}", input, c)));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::compile_with_set_operations;
+
+    fn nfa(pattern: &str, table: &mut CharSetTable) -> Graph {
+        compile_with_set_operations(pattern, table).unwrap()
+    }
+
+    /// 空语言与最短的被接受的串
+    #[test]
+    fn test_is_empty() {
+        let mut table = CharSetTable::new();
+        assert_eq!(nfa("a&b", &mut table).is_empty(&mut table), Verdict::Holds);
+        assert_eq!(
+            nfa("a+&~a", &mut table).is_empty(&mut table),
+            Verdict::Counterexample("aa".to_string())
+        );
+        assert_eq!(
+            nfa("x*", &mut table).is_empty(&mut table),
+            Verdict::Counterexample(String::new())
+        );
+        assert_eq!(
+            nfa("[b-d]c|abcd", &mut table).is_empty(&mut table),
+            Verdict::Counterexample("bc".to_string())
+        );
+    }
+
+    /// 等价与反例
+    #[test]
+    fn test_is_equivalent() {
+        let mut table = CharSetTable::new();
+        let pairs = [
+            ("(a|b)*", "(a*b*)*"),
+            ("a+", "aa*"),
+            ("(ab)*a", "a(ba)*"),
+            ("[a-c]|d", "[a-d]"),
+        ];
+        for (x, y) in pairs {
+            let (x, y) = (nfa(x, &mut table), nfa(y, &mut table));
+            assert_eq!(x.is_equivalent(&y, &mut table), Verdict::Holds);
+        }
+        let x = nfa("(a|b)*abb", &mut table);
+        let y = nfa("(a|b)*bb", &mut table);
+        assert_eq!(
+            x.is_equivalent(&y, &mut table),
+            Verdict::Counterexample("bb".to_string())
+        );
+        let x = nfa("[a-z]+", &mut table);
+        let y = nfa("[a-y]+", &mut table);
+        assert_eq!(
+            x.is_equivalent(&y, &mut table),
+            Verdict::Counterexample("z".to_string())
+        );
+    }
+
+    /// 包含与反例
+    #[test]
+    fn test_is_subset_of() {
+        let mut table = CharSetTable::new();
+        let keyword = nfa("if|else", &mut table);
+        let id = nfa("[a-z]+", &mut table);
+        assert_eq!(keyword.is_subset_of(&id, &mut table), Verdict::Holds);
+        assert_eq!(
+            id.is_subset_of(&keyword, &mut table),
+            Verdict::Counterexample("a".to_string())
+        );
+        let empty = nfa("a&b", &mut table);
+        assert_eq!(empty.is_subset_of(&keyword, &mut table), Verdict::Holds);
+        assert!(empty.is_subset_of(&id, &mut table).holds());
+        assert!(!id.is_subset_of(&empty, &mut table).holds());
+    }
+}
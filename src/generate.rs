@@ -0,0 +1,352 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::char_set::{normalize_ranges, CharSetTable};
+use super::graph::{Category, DriverType, Edge, Graph, LexemeCategory, StateType};
+
+/// 默认的随机游走步数上限,超过后沿预先求出的路径走到结束状态
+const DEFAULT_MAX_STEPS: usize = 32;
+
+impl<C: Category> Graph<C> {
+    /// 最短的被接受的串:在状态上做0-1广度优先搜索,ε边的代价为0,其余边的代价为1,
+    /// CHARSET边取字符集中最小的字符。语言为空时返回None
+    pub fn shortest_witness(&self, table: &CharSetTable) -> Option<String> {
+        let mut visited: HashSet<i32> = HashSet::new();
+        let mut queue: VecDeque<(i32, String)> = VecDeque::new();
+        queue.push_back((0, String::new()));
+        while let Some((state, input)) = queue.pop_front() {
+            if !visited.insert(state) {
+                continue;
+            }
+            if self.is_match_states(&[state]) {
+                return Some(input);
+            }
+            for edge in self.p_edge_table.iter() {
+                if edge.from_state != state || visited.contains(&edge.next_state) {
+                    continue;
+                }
+                match edge_first_char(edge, table) {
+                    None if edge.driver_type == DriverType::NULL => {
+                        queue.push_front((edge.next_state, input.clone()))
+                    }
+                    None => {}
+                    Some(c) => queue.push_back((edge.next_state, format!("{}{}", input, c))),
+                }
+            }
+        }
+        None
+    }
+    /// 按字典序惰性地枚举长度不超过max_len的所有被接受的串,
+    /// 在NFA上按字符从小到大深度优先搜索,字符集很大时可用take截取前若干个
+    pub fn enumerate<'a>(&'a self, max_len: usize, table: &'a CharSetTable) -> Enumerate<'a, C> {
+        let mut alphabet: Vec<(char, char)> = self.edge_ranges(table).concat();
+        normalize_ranges(&mut alphabet);
+        let start = self.epsilon_closure(&[0]);
+        Enumerate {
+            graph: self,
+            table,
+            max_len,
+            stack: vec![Frame {
+                states: start,
+                prefix: String::new(),
+                len: 0,
+                cursor: alphabet.first().map(|range| range.0),
+                emitted: false,
+            }],
+            alphabet,
+        }
+    }
+}
+
+/// 不是ε边时,边上最小的字符
+fn edge_first_char(edge: &Edge, table: &CharSetTable) -> Option<char> {
+    match edge.driver_type {
+        DriverType::NULL => None,
+        DriverType::CHAR => char::from_u32(edge.driver_id as u32),
+        DriverType::CHARSET => table.ranges(edge.driver_id).first().map(|range| range.0),
+    }
+}
+
+/// 深度优先搜索的一层
+struct Frame {
+    /// 读入prefix后到达的NFA状态集
+    states: Vec<i32>,
+    prefix: String,
+    /// prefix的字符数
+    len: usize,
+    /// 下一个要尝试的字符,None表示已尝试完
+    cursor: Option<char>,
+    /// prefix是否已经输出过
+    emitted: bool,
+}
+
+/// 按字典序枚举被接受的串的迭代器,见`Graph::enumerate`
+pub struct Enumerate<'a, C = LexemeCategory> {
+    graph: &'a Graph<C>,
+    table: &'a CharSetTable,
+    max_len: usize,
+    stack: Vec<Frame>,
+    /// 所有边上的字符,已排序合并
+    alphabet: Vec<(char, char)>,
+}
+
+impl<'a, C: Category> Iterator for Enumerate<'a, C> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            let frame = self.stack.last_mut()?;
+            if !frame.emitted {
+                frame.emitted = true;
+                if self.graph.is_match_states(&frame.states) {
+                    return Some(frame.prefix.clone());
+                }
+            }
+            let c = match frame.cursor {
+                Some(c) if frame.len < self.max_len => c,
+                _ => {
+                    self.stack.pop();
+                    continue;
+                }
+            };
+            let (states, prefix, len) = (frame.states.clone(), frame.prefix.clone(), frame.len);
+            frame.cursor = next_char(c).and_then(|next| alphabet_from(&self.alphabet, next));
+            let next_states = self
+                .graph
+                .epsilon_closure(&self.graph.move_by_char(&states, c, self.table));
+            if next_states.is_empty() {
+                continue;
+            }
+            self.stack.push(Frame {
+                states: next_states,
+                prefix: format!("{}{}", prefix, c),
+                len: len + 1,
+                cursor: self.alphabet.first().map(|range| range.0),
+                emitted: false,
+            });
+        }
+    }
+}
+
+/// 字母表中不小于c的最小字符
+fn alphabet_from(alphabet: &[(char, char)], c: char) -> Option<char> {
+    alphabet
+        .iter()
+        .find(|&&(_, to_char)| to_char >= c)
+        .map(|&(from_char, _)| from_char.max(c))
+}
+
+/// 码位加一后的字符,跳过代理区
+fn next_char(c: char) -> Option<char> {
+    match c as u32 {
+        0xD7FF => Some('\u{E000}'),
+        code => char::from_u32(code + 1),
+    }
+}
+
+/// 带种子的随机串生成器:在NFA上随机游走,在结束状态可以停下,
+/// 经过CHARSET边时在其字符范围中均匀地选取字符。
+/// 游走步数超过上限后沿预先求出的路径走到结束状态,保证生成的串总被接受
+pub struct Sampler<'a, C = LexemeCategory> {
+    graph: &'a Graph<C>,
+    table: &'a CharSetTable,
+    /// splitmix64的状态
+    seed: u64,
+    max_steps: usize,
+    /// 状态 -> 通往结束状态的路径上的下一条边;无法到达结束状态时为None
+    next_edge: Vec<Option<usize>>,
+    /// 状态能否到达结束状态
+    live: Vec<bool>,
+}
+
+impl<'a, C: Category> Sampler<'a, C> {
+    /// 构造函数,相同的种子生成相同的串序列
+    pub fn new(graph: &'a Graph<C>, table: &'a CharSetTable, seed: u64) -> Sampler<'a, C> {
+        // 从结束状态出发在反向边上做广度优先搜索,每个状态的下一条边指向先出队的状态,不会成环
+        let len = graph.p_state_table.len();
+        let mut next_edge: Vec<Option<usize>> = vec![None; len];
+        let mut live = vec![false; len];
+        let mut done = vec![false; len];
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        for state in graph.p_state_table.iter() {
+            if state.state_type == StateType::MATCH {
+                live[state.state_id as usize] = true;
+                queue.push_back(state.state_id as usize);
+            }
+        }
+        while let Some(state) = queue.pop_front() {
+            if done[state] {
+                continue;
+            }
+            done[state] = true;
+            for (index, edge) in graph.p_edge_table.iter().enumerate() {
+                let from = edge.from_state as usize;
+                if edge.next_state as usize != state || live[from] {
+                    continue;
+                }
+                live[from] = true;
+                next_edge[from] = Some(index);
+                if edge.driver_type == DriverType::NULL {
+                    queue.push_front(from);
+                } else {
+                    queue.push_back(from);
+                }
+            }
+        }
+        Sampler {
+            graph,
+            table,
+            seed,
+            max_steps: DEFAULT_MAX_STEPS,
+            next_edge,
+            live,
+        }
+    }
+    /// 设置随机游走的步数上限(含ε边)
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+    /// 生成一个被接受的串,语言为空时返回None
+    pub fn sample(&mut self) -> Option<String> {
+        if !self.live.first().copied().unwrap_or(false) {
+            return None;
+        }
+        let mut result = String::new();
+        let mut state = 0usize;
+        let mut steps = 0;
+        loop {
+            let is_match = self.graph.p_state_table[state].state_type == StateType::MATCH;
+            let edge = if steps < self.max_steps {
+                let choices: Vec<&Edge> = self
+                    .graph
+                    .p_edge_table
+                    .iter()
+                    .filter(|edge| {
+                        edge.from_state as usize == state && self.live[edge.next_state as usize]
+                    })
+                    .collect();
+                // 结束状态上"停下"也是一个选项
+                let count = choices.len() + is_match as usize;
+                match self.next_below(count as u64) as usize {
+                    index if index < choices.len() => choices[index],
+                    _ => return Some(result),
+                }
+            } else if is_match {
+                return Some(result);
+            } else {
+                &self.graph.p_edge_table[self.next_edge[state].unwrap()]
+            };
+            if let Some(c) = self.pick_char(edge) {
+                result.push(c);
+            }
+            state = edge.next_state as usize;
+            steps += 1;
+        }
+    }
+
+    /// splitmix64
+    fn next_u64(&mut self) -> u64 {
+        self.seed = self.seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.seed;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+    /// [0, bound)中的随机数
+    fn next_below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+    /// 在边的字符中均匀地选取一个,ε边返回None
+    fn pick_char(&mut self, edge: &Edge) -> Option<char> {
+        let ranges = match edge.driver_type {
+            DriverType::NULL => return None,
+            DriverType::CHAR => return char::from_u32(edge.driver_id as u32),
+            DriverType::CHARSET => self.table.ranges(edge.driver_id),
+        };
+        let total: u64 = ranges
+            .iter()
+            .map(|&(from_char, to_char)| (to_char as u64) - (from_char as u64) + 1)
+            .sum();
+        let mut index = self.next_below(total);
+        for &(from_char, to_char) in ranges.iter() {
+            let size = (to_char as u64) - (from_char as u64) + 1;
+            if index < size {
+                return char::from_u32(from_char as u32 + index as u32);
+            }
+            index -= size;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::compile_with_set_operations;
+
+    /// 最短的被接受的串
+    #[test]
+    fn test_shortest_witness() {
+        let mut table = CharSetTable::new();
+        let cases = [
+            ("(a|b)*abb", Some("abb")),
+            ("[b-d]c|abcd", Some("bc")),
+            ("x*", Some("")),
+            ("a&b", None),
+        ];
+        for (pattern, expected) in cases {
+            let graph = compile_with_set_operations(pattern, &mut table).unwrap();
+            assert_eq!(
+                graph.shortest_witness(&table).as_deref(),
+                expected,
+                "{}",
+                pattern
+            );
+        }
+    }
+
+    /// 按字典序枚举
+    #[test]
+    fn test_enumerate() {
+        let mut table = CharSetTable::new();
+        let graph = compile_with_set_operations("(a|b)*", &mut table).unwrap();
+        let strings: Vec<String> = graph.enumerate(2, &table).collect();
+        assert_eq!(strings, ["", "a", "aa", "ab", "b", "ba", "bb"]);
+        let graph = compile_with_set_operations("b|a|ba?|[x-z]c", &mut table).unwrap();
+        let strings: Vec<String> = graph.enumerate(3, &table).collect();
+        assert_eq!(strings, ["a", "b", "ba", "xc", "yc", "zc"]);
+        // 字符集很大时惰性枚举
+        let graph = compile_with_set_operations(".+", &mut table).unwrap();
+        let strings: Vec<String> = graph.enumerate(2, &table).take(3).collect();
+        assert_eq!(strings, ["\0", "\0\0", "\0\u{1}"]);
+    }
+
+    /// 随机生成的串都被接受,相同的种子得到相同的结果
+    #[test]
+    fn test_sampler() {
+        let mut table = CharSetTable::new();
+        let graph = compile_with_set_operations("[α-ω]+(,[0-9]{2})*|x?", &mut table).unwrap();
+        let mut sampler = Sampler::new(&graph, &table, 42);
+        let samples: Vec<String> = (0..50).map(|_| sampler.sample().unwrap()).collect();
+        for sample in samples.iter() {
+            assert!(graph.simulate(sample, &table), "{:?}", sample);
+        }
+        assert!(samples.iter().any(|sample| sample.contains(',')));
+        let mut again = Sampler::new(&graph, &table, 42);
+        let repeated: Vec<String> = (0..50).map(|_| again.sample().unwrap()).collect();
+        assert_eq!(samples, repeated);
+        let mut other = Sampler::new(&graph, &table, 7);
+        let different: Vec<String> = (0..50).map(|_| other.sample().unwrap()).collect();
+        assert_ne!(samples, different);
+        // 步数上限之后沿预先求出的路径结束
+        let graph = compile_with_set_operations("a+b", &mut table).unwrap();
+        let mut sampler = Sampler::new(&graph, &table, 1).with_max_steps(4);
+        for _ in 0..20 {
+            let sample = sampler.sample().unwrap();
+            assert!(graph.simulate(&sample, &table));
+            assert!(sample.len() <= 6, "{}", sample);
+        }
+        let empty = compile_with_set_operations("a&b", &mut table).unwrap();
+        assert_eq!(Sampler::new(&empty, &table, 0).sample(), None);
+    }
+}
@@ -6,8 +6,10 @@ use std::{
     fmt::Display,
 };
 
+use super::char_set::{case_fold_ranges, normalize_ranges, partition_ranges, CharSetTable};
+
 /// 词的**类别**
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
 pub enum LexemeCategory {
     /// 整数常量
     INTEGER_CONST,
@@ -33,15 +35,27 @@ pub enum LexemeCategory {
     EMPTY,
 }
 
-/// 图的数据结构
+/// 词的类别需要满足的约束,可以用自定义的类型代替LexemeCategory
+pub trait Category: Copy + Eq + std::fmt::Debug + std::hash::Hash {
+    /// 表示没有类别的值,非结束状态的类别总是该值
+    fn empty() -> Self;
+}
+
+impl Category for LexemeCategory {
+    fn empty() -> Self {
+        LexemeCategory::EMPTY
+    }
+}
+
+/// 图的数据结构,状态的类别为C
 #[derive(Clone, Debug)]
-pub struct Graph {
+pub struct Graph<C = LexemeCategory> {
     pub graph_id: i32,
     pub num_of_states: i32,
     pub p_edge_table: Vec<Edge>,
-    pub p_state_table: Vec<State>,
+    pub p_state_table: Vec<State<C>>,
 }
-impl Display for Graph {
+impl<C: std::fmt::Debug> Display for Graph<C> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
@@ -57,7 +71,8 @@ pub struct Edge {
     pub from_state: i32,
     /// 该边的经过转换后的状态id
     pub next_state: i32,
-    /// 驱动转换的字符或字符集id,为-1时表示空转换
+    /// 驱动转换的字符或字符集id,为-1时表示空转换；
+    /// driver_type为CHAR时是字符的码位,为CHARSET时是字符集表中的index_id
     pub driver_id: i32,
     /// 驱动转换的类型
     pub driver_type: DriverType,
@@ -65,12 +80,12 @@ pub struct Edge {
 
 /// State数据结构，存储状态
 #[derive(Clone, Debug)]
-pub struct State {
+pub struct State<C = LexemeCategory> {
     pub state_id: i32,
     /// MATCH or UNMATCH
     pub state_type: StateType,
     /// 词法属性
-    pub category: LexemeCategory,
+    pub category: C,
 }
 
 #[derive(PartialEq, Eq, Clone, Debug)]
@@ -91,9 +106,9 @@ pub enum StateType {
     UNMATCH,
 }
 
-impl Graph {
+impl<C: Category> Graph<C> {
     /// 添加一个状态
-    fn add_state(&mut self, state_type: StateType, category: LexemeCategory) -> i32 {
+    fn add_state(&mut self, state_type: StateType, category: C) -> i32 {
         // find the max state_id and plus 1,default 0
         let mut state_id = -1;
         for item in self.p_state_table.iter() {
@@ -126,6 +141,26 @@ impl Graph {
             driver_type,
         })
     }
+    /// 添加一条由字符范围驱动的边:只含一个字符时为CHAR边,否则为CHARSET边,字符集加入到table中
+    pub(crate) fn add_ranges_edge(
+        &mut self,
+        from_state: i32,
+        next_state: i32,
+        ranges: &[(char, char)],
+        table: &mut CharSetTable,
+    ) {
+        let mut ranges = ranges.to_vec();
+        normalize_ranges(&mut ranges);
+        match ranges[..] {
+            [(from_char, to_char)] if from_char == to_char => {
+                self.add_edge(from_state, next_state, DriverType::CHAR, from_char as i32)
+            }
+            _ => {
+                let driver_id = table.from_ranges(&ranges);
+                self.add_edge(from_state, next_state, DriverType::CHARSET, driver_id)
+            }
+        }
+    }
     /// 开始状态是否有入边
     fn is_start_state_has_edge_in(&self) -> bool {
         for item in self.p_edge_table.iter() {
@@ -137,8 +172,8 @@ impl Graph {
     }
     /// 开始状态的category是否为空
     fn is_start_state_category_empty(&self) -> bool {
-        match self.p_state_table.get(0) {
-            Some(value) if value.category == LexemeCategory::EMPTY => true,
+        match self.p_state_table.first() {
+            Some(value) if value.category == C::empty() => true,
             Some(_) => false,
             None => panic!("no from_state in this graph"),
         }
@@ -154,8 +189,8 @@ impl Graph {
     }
     /// 结束状态的category是否为空
     fn is_end_state_category_empty(&self) -> bool {
-        match self.p_state_table.get(self.p_state_table.len() - 1) {
-            Some(value) if value.category == LexemeCategory::EMPTY => true,
+        match self.p_state_table.last() {
+            Some(value) if value.category == C::empty() => true,
             Some(_) => false,
             None => panic!("no end_state in this graph"),
         }
@@ -168,12 +203,12 @@ impl Graph {
         if self.is_start_state_has_edge_in() {
             // 重新排列state序号
             for item in self.p_state_table.iter_mut() {
-                item.state_id = item.state_id + 1;
+                item.state_id += 1;
             }
             // 重新设置edge中存储的state序号
             for item in self.p_edge_table.iter_mut() {
-                item.from_state = item.from_state + 1;
-                item.next_state = item.next_state + 1;
+                item.from_state += 1;
+                item.next_state += 1;
             }
             // 在原来的开始状态前加一个状态
             self.p_state_table.insert(
@@ -181,7 +216,7 @@ impl Graph {
                 State {
                     state_id: 0,
                     state_type: StateType::UNMATCH,
-                    category: LexemeCategory::EMPTY,
+                    category: C::empty(),
                 },
             );
             // 加入一条由当前的开始状态到原来的开始状态的边
@@ -204,7 +239,7 @@ impl Graph {
             self.p_state_table.push(State {
                 state_id: self.p_state_table.len() as i32,
                 state_type: StateType::MATCH,
-                category: LexemeCategory::EMPTY,
+                category: C::empty(),
             });
             // 加入一条原来的结束状态到当前的结束状态的边
             self.p_edge_table.push(Edge {
@@ -216,7 +251,7 @@ impl Graph {
         }
         // 第二步改造
         // 如果第一步转换后的NFA结束状态的category属性值不为空，具体做法同第一步的结束状态无出边的情况
-        if self.p_state_table[self.p_state_table.len() - 1].category != LexemeCategory::EMPTY {
+        if self.p_state_table[self.p_state_table.len() - 1].category != C::empty() {
             // 原来的结束状态的state_type变为UNMATCH,即由结束状态改为非结束状态
             let end_pos = self.p_state_table.len() - 1;
             self.p_state_table[end_pos].state_type = StateType::UNMATCH;
@@ -224,7 +259,7 @@ impl Graph {
             self.p_state_table.push(State {
                 state_id: self.p_state_table.len() as i32,
                 state_type: StateType::MATCH,
-                category: LexemeCategory::EMPTY,
+                category: C::empty(),
             });
             // 加入一条原来的结束状态到当前的结束状态的边
             self.p_edge_table.push(Edge {
@@ -237,20 +272,20 @@ impl Graph {
     }
 
     /// 针对一个字符或者一个字符集，创建其NFA。其NFA的基本特征是：只包含两个状态（0状态和1状态），且结束状态（即1状态）无出边
-    pub fn generate_basic_nfa(driver_type: DriverType, driver_id: i32) -> Graph {
+    pub fn generate_basic_nfa(driver_type: DriverType, driver_id: i32) -> Graph<C> {
         let mut new_graph = Graph {
             graph_id: 0,
             num_of_states: 2,
             p_edge_table: Vec::new(),
             p_state_table: Vec::new(),
         };
-        let from_state = new_graph.add_state(StateType::UNMATCH, LexemeCategory::EMPTY);
-        let next_state = new_graph.add_state(StateType::MATCH, LexemeCategory::EMPTY);
+        let from_state = new_graph.add_state(StateType::UNMATCH, C::empty());
+        let next_state = new_graph.add_state(StateType::MATCH, C::empty());
         new_graph.add_edge(from_state, next_state, driver_type, driver_id);
         new_graph
     }
     /// 最简NFA构造法:**并运算** s|t
-    pub fn union(&self, graph: &Graph) -> Graph {
+    pub fn union(&self, graph: &Graph<C>) -> Graph<C> {
         //TODO 判断传入的graph是否合法，目前仅简单的assert
         assert!(self.p_state_table.len() >= 2);
         assert!(graph.p_state_table.len() >= 2);
@@ -267,58 +302,68 @@ impl Graph {
             s.equivalent_transform();
             t.equivalent_transform();
         }
+        let (s_len, t_len) = (s.p_state_table.len(), t.p_state_table.len());
+        // 合并后的结束状态序号
+        let end_state = (s_len + t_len - 3) as i32;
 
         // 用最简NFA构造法
         // 初始化state_table
-        let mut p_state_table: Vec<State> = Vec::new();
+        let mut p_state_table: Vec<State<C>> = Vec::new();
         // 加入开始状态
         p_state_table.push(State {
             state_id: 0,
             state_type: StateType::UNMATCH,
-            category: LexemeCategory::EMPTY,
+            category: C::empty(),
         });
         // 序号重排列后加入将原来的两个p_state_table
-        for (index, item) in self.p_state_table.iter().enumerate() {
+        for (index, item) in s.p_state_table.iter().enumerate() {
             //开始状态和结束状态忽略
-            if index == 0 || index == self.p_state_table.len() - 1 {
+            if index == 0 || index == s_len - 1 {
                 continue;
             }
             p_state_table.push(item.clone());
         }
-        for (index, item) in graph.p_state_table.iter().enumerate() {
-            if index == 0 || index == graph.p_state_table.len() - 1 {
+        for (index, item) in t.p_state_table.iter().enumerate() {
+            if index == 0 || index == t_len - 1 {
                 continue;
             }
             let mut next_state = item.clone();
-            // graph加入p_state_table的索引应等于原索引(index)+self的结束状态序号-1
-            next_state.state_id = (index + self.p_state_table.len() - 2) as i32;
+            // t加入p_state_table的索引应等于原索引(index)+s的结束状态序号-1
+            next_state.state_id = (index + s_len - 2) as i32;
             p_state_table.push(next_state);
         }
         // 加入结束状态
         p_state_table.push(State {
-            state_id: p_state_table.len() as i32,
+            state_id: end_state,
             state_type: StateType::MATCH,
-            category: LexemeCategory::EMPTY,
+            category: C::empty(),
         });
         // 初始化边数组p_edge_table
         let mut p_edge_table: Vec<Edge> = Vec::new();
         // 把原来的边更改到正确的序号后加入p_edge_table中
-        for (index, item) in self.p_edge_table.iter().enumerate() {
+        for item in s.p_edge_table.iter() {
             let mut edge = item.clone();
-            // 如果该边的next_state为原来的结束状态的state_id(self.p_state_table.len()-1)改为当前的结束状态的state_id
-            if item.next_state == (self.p_state_table.len() - 1) as i32 {
-                edge.next_state = (p_state_table.len() - 1) as i32;
+            // 如果该边的next_state为原来的结束状态的state_id(s_len-1)改为当前的结束状态的state_id
+            if item.next_state == (s_len - 1) as i32 {
+                edge.next_state = end_state;
             }
             p_edge_table.push(edge);
         }
-        for (index, item) in graph.p_edge_table.iter().enumerate() {
-            let mut edge = item.clone();
-            edge.from_state = (item.from_state as usize + self.p_state_table.len() - 2) as i32;
-            edge.next_state = (item.from_state as usize + self.p_state_table.len() - 2) as i32;
-            // 如果该边的from_state为原来的开始状态的state_id即0，改为当前的开始状态0
-            if item.from_state == 0 {
-                edge.from_state = 0;
+        let shift = |state: i32| -> i32 {
+            if state == 0 {
+                // 原来的开始状态即当前的开始状态0
+                0
+            } else if state == (t_len - 1) as i32 {
+                // 原来的结束状态即当前的结束状态
+                end_state
+            } else {
+                state + s_len as i32 - 2
             }
+        };
+        for item in t.p_edge_table.iter() {
+            let mut edge = item.clone();
+            edge.from_state = shift(item.from_state);
+            edge.next_state = shift(item.next_state);
             p_edge_table.push(edge);
         }
         Graph {
@@ -329,9 +374,9 @@ impl Graph {
         }
     }
     /// 最简NFA构造法：**连接运算** s·t
-    pub fn product(&self, graph: &Graph) -> Graph {
+    pub fn product(&self, graph: &Graph<C>) -> Graph<C> {
         // 分为两种情况,以下为共同行为
-        let mut p_state_table: Vec<State> = Vec::new();
+        let mut p_state_table: Vec<State<C>> = Vec::new();
         let mut p_edge_table: Vec<Edge> = Vec::new();
         for item in self.p_state_table.iter() {
             p_state_table.push(item.clone());
@@ -369,6 +414,10 @@ impl Graph {
         // 其他情形
         } else {
             for (index, item) in graph.p_state_table.iter().enumerate() {
+                // t的开始状态与s的结束状态合并为同一个状态
+                if index == 0 {
+                    continue;
+                }
                 let mut state = item.clone();
                 // t的状态在新的graph中的序号应等于原序号+s的终止状态序号
                 state.state_id = (self.p_state_table.len() - 1 + index) as i32;
@@ -389,9 +438,9 @@ impl Graph {
         }
     }
     /// 正闭包运算
-    pub fn plus_closure(&self) -> Graph {
+    pub fn plus_closure(&self) -> Graph<C> {
         // 分为四种情况
-        let mut p_state_table: Vec<State> = Vec::new();
+        let mut p_state_table: Vec<State<C>> = Vec::new();
         let mut p_edge_table: Vec<Edge> = Vec::new();
         // 开始状态有入边且结束状态有出边
         if self.is_start_state_has_edge_in() && self.is_end_state_has_edge_out() {
@@ -399,22 +448,22 @@ impl Graph {
             p_state_table.push(State {
                 state_id: 0,
                 state_type: StateType::UNMATCH,
-                category: LexemeCategory::EMPTY,
+                category: C::empty(),
             });
             // 原来的state更新序号后加入p_state_table
             for item in self.p_state_table.iter() {
                 let mut state = item.clone();
-                state.state_id = state.state_id + 1;
+                state.state_id += 1;
                 p_state_table.push(state);
             }
             // 终止状态的MATCH转为UNMATCH
-            let end_pos = p_edge_table.len() - 1;
+            let end_pos = p_state_table.len() - 1;
             p_state_table[end_pos].state_type = StateType::UNMATCH;
             // 终止状态后加一个状态作为新的终止状态
             p_state_table.push(State {
                 state_id: p_state_table.len() as i32,
                 state_type: StateType::MATCH,
-                category: LexemeCategory::EMPTY,
+                category: C::empty(),
             });
             // 加入当前开始状态到原开始状态的空转换
             p_edge_table.push(Edge {
@@ -439,8 +488,8 @@ impl Graph {
             });
             // 加入一条原结束状态到当前结束状态的空转换
             p_edge_table.push(Edge {
-                from_state: p_state_table.len() as i32 - 1,
-                next_state: p_state_table.len() as i32 - 2,
+                from_state: p_state_table.len() as i32 - 2,
+                next_state: p_state_table.len() as i32 - 1,
                 driver_id: -1,
                 driver_type: DriverType::NULL,
             })
@@ -451,12 +500,12 @@ impl Graph {
             p_state_table.push(State {
                 state_id: 0,
                 state_type: StateType::UNMATCH,
-                category: LexemeCategory::EMPTY,
+                category: C::empty(),
             });
             // 原来的state更新序号后加入p_state_table
             for item in self.p_state_table.iter() {
                 let mut state = item.clone();
-                state.state_id = state.state_id + 1;
+                state.state_id += 1;
                 p_state_table.push(state);
             }
             // 加入当前开始状态到原开始状态的空转换
@@ -488,13 +537,13 @@ impl Graph {
             // 直接复制一份原来的p_edge_table
             p_edge_table = self.p_edge_table.clone();
             // 终止状态的MATCH转为UNMATCH
-            let end_pos = p_edge_table.len() - 1;
+            let end_pos = p_state_table.len() - 1;
             p_state_table[end_pos].state_type = StateType::UNMATCH;
             // 终止状态后加一个状态作为新的终止状态
             p_state_table.push(State {
                 state_id: p_state_table.len() as i32,
                 state_type: StateType::MATCH,
-                category: LexemeCategory::EMPTY,
+                category: C::empty(),
             });
             // 加入一条原终止状态到开始状态的空转换
             p_edge_table.push(Edge {
@@ -505,8 +554,8 @@ impl Graph {
             });
             // 加入一条原终止状态到当前终止状态的空转换
             p_edge_table.push(Edge {
-                from_state: p_state_table.len() as i32 - 1,
-                next_state: p_state_table.len() as i32 - 2,
+                from_state: p_state_table.len() as i32 - 2,
+                next_state: p_state_table.len() as i32 - 1,
                 driver_id: -1,
                 driver_type: DriverType::NULL,
             })
@@ -529,13 +578,13 @@ impl Graph {
 
         Graph {
             graph_id: 0,
-            num_of_states: p_state_table.len() as i32 - 1,
+            num_of_states: p_state_table.len() as i32,
             p_edge_table,
             p_state_table,
         }
     }
     /// 闭包运算
-    pub fn closure(&self) -> Graph {
+    pub fn closure(&self) -> Graph<C> {
         // 直接先进行一次正闭包运算
         let mut graph = self.plus_closure();
         // 加入一条开始状态到结束状态的边
@@ -548,57 +597,49 @@ impl Graph {
         graph
     }
     /// 0或1运算即?
-    pub fn zero_or_one(&self) -> Graph {
-        // 直接先进行一次闭包运算
-        let mut graph = self.closure();
-        // 要删除原结束状态到原开始状态的状态转换边,分为四种情况
-        let mut index: usize = 0;
-        // 开始状态有入边且结束状态有出边
-        if self.is_start_state_has_edge_in() && self.is_end_state_has_edge_out() {
-            index = self.p_edge_table.len() - 3;
-        // 开始状态有入边结束状态无出边
-        } else if self.is_start_state_has_edge_in() && !self.is_end_state_has_edge_out() {
-            index = self.p_edge_table.len() - 2;
-        // 开始状态无入边，结束状态有出边
-        } else if !self.is_start_state_has_edge_in() && self.is_end_state_has_edge_out() {
-            index = self.p_edge_table.len() - 3;
-        // 开始状态无入边，结束状态无出边
-        } else {
-            index = self.p_edge_table.len() - 2;
-        }
+    pub fn zero_or_one(&self) -> Graph<C> {
+        let mut graph = self.clone();
+        // 开始状态有入边或结束状态有出边时，直接加入开始状态到结束状态的空转换会多接受一些串，
+        // 先进行等价转换，保证开始状态无入边且结束状态无出边
+        graph.equivalent_transform();
+        // 加入一条开始状态到结束状态的空转换
+        let end_state = graph.p_state_table.len() as i32 - 1;
+        graph.add_edge(0, end_state, DriverType::NULL, -1);
+        graph.num_of_states = graph.p_state_table.len() as i32;
         graph
     }
 
-    /// 子集构造法move(T,a):从T中的状态经过**一个**输⼊符号a上的转换可以到达的NFA状态集
-    /// self为nfa的图,state_vec为当前状态的nfa state_id数组,driver_id为字符id当为-1时表示空转换
-    fn move_transfer(&self, state_vec: &Vec<i32>, driver_id: i32) -> Vec<i32> {
+    /// move(T,c):从T中的状态经过输入字符c上的转换可以到达的NFA状态集,
+    /// CHAR边比较字符的码位,CHARSET边到字符集表中判断c是否属于该字符集
+    pub fn move_by_char(&self, state_vec: &[i32], c: char, table: &CharSetTable) -> Vec<i32> {
         let mut trans_vec: Vec<i32> = Vec::new();
-        for &state_id in state_vec.iter() {
-            for edge in self.p_edge_table.iter() {
-                // 如果edge的起始状态为此次遍历的状态，并且驱动字符为driver_id,即与传入的驱动字符相同
-                if edge.from_state == state_id && edge.driver_id == driver_id {
-                    trans_vec.push(edge.next_state);
-                }
+        for edge in self.p_edge_table.iter() {
+            if !state_vec.contains(&edge.from_state) || trans_vec.contains(&edge.next_state) {
+                continue;
+            }
+            let accepted = match edge.driver_type {
+                DriverType::NULL => false,
+                DriverType::CHAR => edge.driver_id == c as i32,
+                DriverType::CHARSET => table.contains_char(edge.driver_id, c),
+            };
+            if accepted {
+                trans_vec.push(edge.next_state);
             }
         }
         trans_vec
     }
     /// 子集构造法,状态集中的任何状态经**任意条**ε弧而能到达的所有状态的集合(包括自身)
-    fn epsilon_closure(&self, state_vec: &Vec<i32>) -> Vec<i32> {
+    pub fn epsilon_closure(&self, state_vec: &[i32]) -> Vec<i32> {
         // 将ε-closure(T)初始化为T;
-        let mut stack: VecDeque<i32> = state_vec.clone().into();
-        let mut vec_trans: Vec<i32> = state_vec.clone();
-        loop {
-            // while栈stack不空,弹出栈顶元素,
-            let state_id = match stack.pop_back() {
-                Some(value) => value,
-                None => break,
-            };
+        let mut stack: VecDeque<i32> = state_vec.iter().copied().collect();
+        let mut vec_trans: Vec<i32> = state_vec.to_vec();
+        // while栈stack不空,弹出栈顶元素,
+        while let Some(state_id) = stack.pop_back() {
             // 对每个这样的状态u： 从t到u有⼀条标记为ε的边
             for edge in self.p_edge_table.iter() {
                 if edge.from_state == state_id && edge.driver_id == -1 {
                     // if u不在ε-closure(T)中 do begin
-                    if !stack.contains(&(edge.next_state)) {
+                    if !vec_trans.contains(&(edge.next_state)) {
                         vec_trans.push(edge.next_state);
                         stack.push_back(edge.next_state);
                     }
@@ -606,129 +647,181 @@ impl Graph {
             }
         }
         vec_trans.sort();
+        vec_trans.dedup();
         vec_trans
     }
-    /// Dtran
-    fn d_tran(
-        &mut self,
-        d_states: &Vec<Vec<i32>>,
-        from_state_vec: &Vec<i32>,
-        driver_id: i32,
-        driver_type: DriverType,
-        to_state_vec: &Vec<i32>,
-    ) {
-        // 找到from_state和next_state
-        let mut from_state = -1;
-        let mut next_state = -1;
-        let mut flag = 0;
-        for (index, item) in d_states.iter().enumerate() {
-            if flag == 2 {
-                break;
-            }
-            if item.cmp(from_state_vec) == Ordering::Equal {
-                from_state = index as i32;
-                flag += 1;
-            }
-            if item.cmp(to_state_vec) == Ordering::Equal {
-                next_state = index as i32;
-                flag += 1;
+    /// 状态集中是否含有结束状态
+    pub fn is_match_states(&self, state_vec: &[i32]) -> bool {
+        self.p_state_table.iter().any(|state| {
+            state.state_type == StateType::MATCH && state_vec.contains(&state.state_id)
+        })
+    }
+    /// NFA模拟:从开始状态出发逐个读入字符,判断整个输入串能否被该图接受
+    pub fn simulate(&self, input: &str, table: &CharSetTable) -> bool {
+        let mut state_vec = self.epsilon_closure(&[0]);
+        for c in input.chars() {
+            if state_vec.is_empty() {
+                return false;
             }
+            state_vec = self.epsilon_closure(&self.move_by_char(&state_vec, c, table));
         }
-        let mut if_exists_from_state = false;
-        let mut if_exists_next_state = false;
-        for item in self.p_state_table.iter() {
-            if item.state_id == from_state {
-                if_exists_from_state = true;
-            }
-            if item.state_id == next_state {
-                if_exists_next_state = true;
+        self.is_match_states(&state_vec)
+    }
+    /// 大小写不敏感变换:把CHAR边和CHARSET边的驱动字符换成其简单大小写折叠的等价类,
+    /// 新的字符集加入到字符集表中;需在确定化之前进行
+    pub fn case_insensitive(&self, table: &mut CharSetTable) -> Graph<C> {
+        let mut graph = self.clone();
+        for edge in graph.p_edge_table.iter_mut() {
+            let ranges = match edge.driver_type {
+                DriverType::NULL => continue,
+                DriverType::CHAR => match char::from_u32(edge.driver_id as u32) {
+                    Some(c) => vec![(c, c)],
+                    None => continue,
+                },
+                DriverType::CHARSET => table.ranges(edge.driver_id),
+            };
+            let folded = case_fold_ranges(&ranges);
+            // 没有其他大小写形式的字符不用改变
+            if folded == ranges {
+                continue;
             }
+            edge.driver_type = DriverType::CHARSET;
+            edge.driver_id = table.from_ranges(&folded);
         }
-        if !if_exists_from_state {
-            self.p_state_table.push(State {
-                state_id: from_state,
-                state_type: StateType::UNMATCH,
-                category: LexemeCategory::EMPTY,
-            });
+        graph
+    }
+    /// 换成种别类型D:结束状态的种别为category,其他状态为D::empty(),状态与边不变
+    pub fn with_category<D: Category>(&self, category: D) -> Graph<D> {
+        Graph {
+            graph_id: self.graph_id,
+            num_of_states: self.num_of_states,
+            p_edge_table: self.p_edge_table.clone(),
+            p_state_table: self
+                .p_state_table
+                .iter()
+                .map(|state| State {
+                    state_id: state.state_id,
+                    state_type: state.state_type.clone(),
+                    category: if state.state_type == StateType::MATCH {
+                        category
+                    } else {
+                        D::empty()
+                    },
+                })
+                .collect(),
         }
-        if !if_exists_next_state {
-            self.p_state_table.push(State {
-                state_id: next_state,
-                state_type: StateType::UNMATCH,
-                category: LexemeCategory::EMPTY,
-            });
+    }
+    /// 输入字母表:把所有CHAR边与CHARSET边的字符加细为互不相交的等价类,
+    /// 同一个等价类中的字符在任何状态上的转换都相同
+    pub(crate) fn alphabet_classes(&self, table: &CharSetTable) -> Vec<Vec<(char, char)>> {
+        partition_ranges(&self.edge_ranges(table))
+    }
+    /// 所有CHAR边与CHARSET边的字符范围,去掉重复的
+    pub(crate) fn edge_ranges(&self, table: &CharSetTable) -> Vec<Vec<(char, char)>> {
+        let mut sets: Vec<Vec<(char, char)>> = Vec::new();
+        for edge in self.p_edge_table.iter() {
+            let ranges = match edge.driver_type {
+                DriverType::NULL => continue,
+                DriverType::CHAR => match char::from_u32(edge.driver_id as u32) {
+                    Some(c) => vec![(c, c)],
+                    None => continue,
+                },
+                DriverType::CHARSET => table.ranges(edge.driver_id),
+            };
+            if !sets.contains(&ranges) {
+                sets.push(ranges);
+            }
         }
-        self.p_edge_table.push(Edge {
-            from_state,
-            next_state,
-            driver_id,
-            driver_type,
-        });
+        sets
     }
-    /// 将NFA转化为DFA
-    pub fn nfa_to_dfa(&self) -> Graph {
+    /// 状态集的种别:其中序号最小的、种别不为EMPTY的结束状态的种别
+    fn category_of_states(&self, state_vec: &[i32]) -> C {
+        self.p_state_table
+            .iter()
+            .filter(|state| {
+                state.state_type == StateType::MATCH
+                    && state.category != C::empty()
+                    && state_vec.contains(&state.state_id)
+            })
+            .min_by_key(|state| state.state_id)
+            .map_or(C::empty(), |state| state.category)
+    }
+    /// 用子集构造法将NFA转化为DFA:输入符号为字母表的等价类,只含一个字符的等价类生成CHAR边,
+    /// 否则生成CHARSET边并把字符集加入到table中;DFA的0状态为开始状态,
+    /// 含有NFA结束状态的状态集为结束状态,不生成空状态集对应的死状态
+    pub fn nfa_to_dfa(&self, table: &mut CharSetTable) -> Graph<C> {
+        self.subset_construction(table).0
+    }
+    /// 子集构造法,同时返回DFA每个状态对应的NFA状态集(Dstates)
+    pub(crate) fn subset_construction(
+        &self,
+        table: &mut CharSetTable,
+    ) -> (Graph<C>, Vec<Vec<i32>>) {
         let mut dfa = Graph {
             graph_id: self.graph_id,
             num_of_states: 0,
             p_edge_table: Vec::new(),
             p_state_table: Vec::new(),
         };
-
-        // 找到所有输入字符的id和type
-        let mut char_vec: HashMap<i32, DriverType> = HashMap::new();
-        for edge in self.p_edge_table.iter() {
-            if !(edge.driver_type == DriverType::NULL) {
-                char_vec.insert(edge.driver_id, edge.driver_type.clone());
-            }
-        }
-        // 计算epsion_closure(0)
-        let dfa_state0 = self.epsilon_closure(&vec![0]);
-        // Dstates
-        let mut d_states: Vec<Vec<i32>> = Vec::new();
-        // 初始时， ε-closure(s0)是Dstates中唯⼀的状态且未被标记；
-        // 将未标记的状态放到stack中
-        let mut stack: VecDeque<Vec<i32>> = VecDeque::new();
-        stack.push_back(dfa_state0.clone());
-        d_states.push(dfa_state0);
-        // 加入开始状态
-        let mut state_id = 0;
-        dfa.p_state_table.push(State {
-            state_id,
-            state_type: StateType::UNMATCH,
-            category: LexemeCategory::EMPTY,
-        });
+        let classes = self.alphabet_classes(table);
+        let mut drivers: Vec<Option<(DriverType, i32)>> = vec![None; classes.len()];
+        // 初始时, ε-closure(s0)是Dstates中唯⼀的状态且未被标记
+        let dfa_state0 = self.epsilon_closure(&[0]);
+        let mut d_states: Vec<Vec<i32>> = vec![dfa_state0];
+        let mut marked = 0;
         // while Dstates中存在⼀个未标记的状态T do begin
-        loop {
-            // 标记T:弹出栈
-            let unsigned_state = match stack.pop_back() {
-                Some(value) => value,
-                None => break,
-            };
-            //for 每个输⼊符号a do begin
-            for (&driver_id, driver_type) in char_vec.iter() {
-                // U := ε-closure(move(T， a));
-                let next_unsigned_state =
-                    self.epsilon_closure(&(self.move_transfer(&unsigned_state, driver_id)));
-                // if U没在Dstates中 then
-                if !d_states.contains(&next_unsigned_state) {
-                    // 将U作为⼀个未标记的状态添加到Dstates中；
-                    stack.push_back(next_unsigned_state.clone());
-                    d_states.push(unsigned_state.clone());
+        while marked < d_states.len() {
+            // 标记T
+            let from_state = marked as i32;
+            marked += 1;
+            let unmarked_state = d_states[from_state as usize].clone();
+            // for 每个输⼊符号a do begin
+            for (index, class) in classes.iter().enumerate() {
+                // U := ε-closure(move(T, a)),等价类中任取一个字符即可
+                let next_state_vec =
+                    self.epsilon_closure(&self.move_by_char(&unmarked_state, class[0].0, table));
+                if next_state_vec.is_empty() {
+                    continue;
                 }
-                dfa.d_tran(
-                    &d_states,
-                    &unsigned_state,
+                // if U没在Dstates中 then 将U作为⼀个未标记的状态添加到Dstates中
+                let next_state = match d_states.iter().position(|item| *item == next_state_vec) {
+                    Some(position) => position as i32,
+                    None => {
+                        d_states.push(next_state_vec);
+                        d_states.len() as i32 - 1
+                    }
+                };
+                // Dtran[T, a] := U
+                let (driver_type, driver_id) = drivers[index]
+                    .get_or_insert_with(|| match class[..] {
+                        [(from_char, to_char)] if from_char == to_char => {
+                            (DriverType::CHAR, from_char as i32)
+                        }
+                        _ => (DriverType::CHARSET, table.from_ranges(class)),
+                    })
+                    .clone();
+                dfa.p_edge_table.push(Edge {
+                    from_state,
+                    next_state,
                     driver_id,
-                    driver_type.clone(),
-                    &next_unsigned_state,
-                );
+                    driver_type,
+                });
             }
         }
-
-        dfa.num_of_states = dfa.p_state_table.len() as i32 - 1;
-        let end_pos = dfa.p_state_table.len() - 1;
-        dfa.p_state_table[end_pos].state_type = StateType::MATCH;
-        dfa
+        for (state_id, state_vec) in d_states.iter().enumerate() {
+            let state_type = if self.is_match_states(state_vec) {
+                StateType::MATCH
+            } else {
+                StateType::UNMATCH
+            };
+            dfa.p_state_table.push(State {
+                state_id: state_id as i32,
+                state_type,
+                category: self.category_of_states(state_vec),
+            });
+        }
+        dfa.num_of_states = dfa.p_state_table.len() as i32;
+        (dfa, d_states)
     }
 }
 
@@ -739,10 +832,131 @@ mod test {
     //针对一个字符或者一个字符集，创建其NFA。其NFA的基本特征是：只包含两个状态（0状态和1状态），且结束状态（即1状态）无出边
     #[test]
     fn test_generate_basic_nfa() {
-        let graph = Graph::generate_basic_nfa(DriverType::CHAR, 0);
+        let graph: Graph = Graph::generate_basic_nfa(DriverType::CHAR, 0);
         println!("{}", graph);
     }
+    fn char_nfa(c: char) -> Graph {
+        Graph::generate_basic_nfa(DriverType::CHAR, c as i32)
+    }
+
+    /// 检查graph接受accept中的串、拒绝reject中的串
+    fn check(graph: &Graph, accept: &[&str], reject: &[&str]) {
+        let table = CharSetTable::new();
+        for input in accept {
+            assert!(graph.simulate(input, &table), "should accept {:?}", input);
+        }
+        for input in reject {
+            assert!(!graph.simulate(input, &table), "should reject {:?}", input);
+        }
+    }
+
     // 并运算 s|t
     #[test]
-    fn test_union() {}
+    fn test_union() {
+        let (a, b, c) = (char_nfa('a'), char_nfa('b'), char_nfa('c'));
+        check(&a.union(&b), &["a", "b"], &["", "ab", "c"]);
+        check(
+            &a.product(&b).union(&c),
+            &["ab", "c"],
+            &["a", "b", "abc", ""],
+        );
+        // 开始状态有入边、结束状态有出边的运算对象
+        check(
+            &a.closure().union(&b.plus_closure()),
+            &["", "aaa", "bb"],
+            &["ab", "ba", "c"],
+        );
+    }
+
+    // 连接运算 st
+    #[test]
+    fn test_product() {
+        let (a, b, c) = (char_nfa('a'), char_nfa('b'), char_nfa('c'));
+        check(&a.product(&b), &["ab"], &["", "a", "b", "ba", "abb"]);
+        check(&a.product(&b.union(&c)), &["ab", "ac"], &["a", "bc", "abc"]);
+        check(
+            &a.closure().product(&b.closure()),
+            &["", "a", "b", "aabbb"],
+            &["ba", "aba"],
+        );
+        check(&a.product(&b).product(&c), &["abc"], &["ab", "bc", "acb"]);
+    }
+
+    // 闭包运算 s*与正闭包运算 s+
+    #[test]
+    fn test_closure() {
+        let (a, b) = (char_nfa('a'), char_nfa('b'));
+        check(&a.closure(), &["", "a", "aaaa"], &["b", "ab"]);
+        check(
+            &a.product(&b).closure(),
+            &["", "ab", "abab"],
+            &["a", "aba", "ba"],
+        );
+        check(&a.union(&b).closure(), &["", "abba", "bbb"], &["c", "abc"]);
+        check(&a.plus_closure(), &["a", "aaa"], &["", "b"]);
+        check(
+            &a.product(&b).plus_closure(),
+            &["ab", "ababab"],
+            &["", "a", "abb"],
+        );
+    }
+
+    // 0或1运算 s?
+    #[test]
+    fn test_zero_or_one() {
+        let (a, b) = (char_nfa('a'), char_nfa('b'));
+        check(&a.zero_or_one(), &["", "a"], &["aa", "b"]);
+        check(
+            &a.product(&b).zero_or_one(),
+            &["", "ab"],
+            &["a", "b", "abab"],
+        );
+        // 闭包再取?不能多接受只含一部分的串
+        check(
+            &a.closure().product(&b).zero_or_one(),
+            &["", "b", "aab"],
+            &["a", "ba", "bb"],
+        );
+        check(
+            &a.zero_or_one().product(&b),
+            &["b", "ab"],
+            &["", "a", "aab"],
+        );
+    }
+
+    /// 子集构造法:由最简NFA得到的(a|b)*abb的DFA有4个状态,与NFA接受相同的串
+    #[test]
+    fn test_nfa_to_dfa() {
+        let mut table = CharSetTable::new();
+        let a: Graph = Graph::generate_basic_nfa(DriverType::CHAR, 'a' as i32);
+        let b = Graph::generate_basic_nfa(DriverType::CHAR, 'b' as i32);
+        let nfa = a.union(&b).closure().product(&a).product(&b).product(&b);
+        let dfa = nfa.nfa_to_dfa(&mut table);
+        assert_eq!(dfa.num_of_states, 4);
+        assert!(dfa
+            .p_edge_table
+            .iter()
+            .all(|edge| edge.driver_type != DriverType::NULL));
+        for input in ["abb", "aabb", "babb", "ab", "abba", ""] {
+            assert_eq!(
+                dfa.simulate(input, &table),
+                nfa.simulate(input, &table),
+                "{}",
+                input
+            );
+        }
+        // 相交的字符集边被加细为互不相交的等价类
+        let digit = table.range('0', '9');
+        let x = Graph::generate_basic_nfa(DriverType::CHAR, '5' as i32).product(&a);
+        let y = Graph::generate_basic_nfa(DriverType::CHARSET, digit).product(&b);
+        let dfa = x.union(&y).nfa_to_dfa(&mut table);
+        for input in ["5a", "5b", "7b", "7a", "5"] {
+            assert_eq!(
+                dfa.simulate(input, &table),
+                input != "7a" && input != "5",
+                "{}",
+                input
+            );
+        }
+    }
 }
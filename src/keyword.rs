@@ -0,0 +1,192 @@
+use super::char_set::simple_case_fold;
+use super::graph::{Category, LexemeCategory};
+use super::tokenizer::Token;
+
+/// 字典树的一个结点
+#[derive(Clone, Debug)]
+struct TrieNode<C> {
+    /// 按字符排序的子结点:(字符, 结点序号)
+    children: Vec<(char, usize)>,
+    /// 从根到该结点的串是关键字时为其种别
+    category: Option<C>,
+}
+
+/// **关键字表**:词法分析把单词归为ID(或构造时指定的种别)后,在字典树中查找其词素,
+/// 是关键字时改为该关键字的种别。这样关键字不必写成单独的规则,DFA不会因此变大
+#[derive(Clone, Debug)]
+pub struct KeywordTable<C = LexemeCategory> {
+    /// nodes[0]为根结点
+    nodes: Vec<TrieNode<C>>,
+    /// 被查找的单词的种别
+    id: C,
+    /// 是否大小写不敏感
+    case_insensitive: bool,
+}
+
+impl Default for KeywordTable {
+    fn default() -> Self {
+        KeywordTable::new()
+    }
+}
+
+impl KeywordTable {
+    /// 在种别为ID的单词中查找关键字的关键字表
+    pub fn new() -> KeywordTable {
+        KeywordTable::for_category(LexemeCategory::ID, false)
+    }
+    /// 大小写不敏感的关键字表,查找时比较两边的简单大小写折叠
+    pub fn case_insensitive() -> KeywordTable {
+        KeywordTable::for_category(LexemeCategory::ID, true)
+    }
+}
+
+impl<C: Category> KeywordTable<C> {
+    /// 在种别为id的单词中查找关键字的关键字表
+    pub fn for_category(id: C, case_insensitive: bool) -> KeywordTable<C> {
+        KeywordTable {
+            nodes: vec![TrieNode {
+                children: Vec::new(),
+                category: None,
+            }],
+            id,
+            case_insensitive,
+        }
+    }
+
+    /// 查找与插入时比较的字符,大小写不敏感时取简单大小写折叠,与正则表达式的(?i)一致
+    fn chars<'s>(&self, text: &'s str) -> impl Iterator<Item = char> + 's {
+        let case_insensitive = self.case_insensitive;
+        text.chars().map(move |c| {
+            if case_insensitive {
+                simple_case_fold(c)
+            } else {
+                c
+            }
+        })
+    }
+    /// 加入一个关键字及其种别,已有该关键字时替换其种别;关键字为空串时panic
+    pub fn insert(&mut self, keyword: &str, category: C) {
+        if keyword.is_empty() {
+            panic!("keyword must not be empty");
+        }
+        let mut node = 0;
+        let chars: Vec<char> = self.chars(keyword).collect();
+        for c in chars {
+            node = match self.nodes[node]
+                .children
+                .binary_search_by_key(&c, |&(child, _)| child)
+            {
+                Ok(index) => self.nodes[node].children[index].1,
+                Err(index) => {
+                    self.nodes.push(TrieNode {
+                        children: Vec::new(),
+                        category: None,
+                    });
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children.insert(index, (c, child));
+                    child
+                }
+            };
+        }
+        self.nodes[node].category = Some(category);
+    }
+    /// text是关键字时返回其种别
+    pub fn lookup(&self, text: &str) -> Option<C> {
+        let mut node = 0;
+        for c in self.chars(text) {
+            let children = &self.nodes[node].children;
+            node = match children.binary_search_by_key(&c, |&(child, _)| child) {
+                Ok(index) => children[index].1,
+                Err(_) => return None,
+            };
+        }
+        self.nodes[node].category
+    }
+    /// 种别为id的单词是关键字时改为关键字的种别,返回是否改变了种别
+    pub fn reclassify(&self, token: &mut Token<C>) -> bool {
+        if token.category != self.id {
+            return false;
+        }
+        match self.lookup(&token.text) {
+            Some(category) => {
+                token.category = category;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::char_set::CharSetTable;
+    use crate::pattern::compile;
+
+    /// 自定义的种别类型,每个关键字一个种别
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    enum Kind {
+        Ident,
+        If,
+        Int,
+        In,
+        Return,
+        None,
+    }
+
+    impl Category for Kind {
+        fn empty() -> Self {
+            Kind::None
+        }
+    }
+
+    /// 查找关键字,前缀与更长的串都不是关键字
+    #[test]
+    fn test_lookup() {
+        let mut keywords = KeywordTable::for_category(Kind::Ident, false);
+        keywords.insert("if", Kind::If);
+        keywords.insert("int", Kind::Int);
+        keywords.insert("in", Kind::In);
+        keywords.insert("return", Kind::Return);
+        assert_eq!(keywords.lookup("int"), Some(Kind::Int));
+        assert_eq!(keywords.lookup("in"), Some(Kind::In));
+        for text in ["i", "ints", "retur", "", "If"] {
+            assert_eq!(keywords.lookup(text), None, "{}", text);
+        }
+        keywords.insert("in", Kind::If);
+        assert_eq!(keywords.lookup("in"), Some(Kind::If));
+    }
+
+    /// 大小写不敏感,按简单大小写折叠比较,与正则表达式的(?i)一致
+    #[test]
+    fn test_case_insensitive() {
+        let mut keywords = KeywordTable::case_insensitive();
+        keywords.insert("SELECT", LexemeCategory::OPERATOR);
+        keywords.insert("Or", LexemeCategory::LOGIC_OPERATOR);
+        keywords.insert("kind", LexemeCategory::OPERATOR);
+        assert_eq!(keywords.lookup("select"), Some(LexemeCategory::OPERATOR));
+        assert_eq!(keywords.lookup("SeLeCt"), Some(LexemeCategory::OPERATOR));
+        assert_eq!(keywords.lookup("OR"), Some(LexemeCategory::LOGIC_OPERATOR));
+        assert_eq!(keywords.lookup("sel"), None);
+        // 开尔文符号K折叠为k,长s折叠为s
+        assert_eq!(
+            keywords.lookup("\u{212A}IND"),
+            Some(LexemeCategory::OPERATOR)
+        );
+        assert_eq!(
+            keywords.lookup("\u{17F}elect"),
+            Some(LexemeCategory::OPERATOR)
+        );
+        // 与正则表达式(?i)select的匹配结果相同
+        let mut table = CharSetTable::new();
+        let pattern = compile("(?i)select", &mut table).unwrap();
+        for text in ["SELECT", "\u{17F}ELECT", "selecT", "seleck", "\u{130}"] {
+            assert_eq!(
+                keywords.lookup(text).is_some(),
+                pattern.simulate(text, &table),
+                "{}",
+                text
+            );
+        }
+    }
+}
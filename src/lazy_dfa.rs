@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+
+use super::char_set::CharSetTable;
+use super::graph::{Category, Graph, LexemeCategory};
+
+/// 默认缓存的DFA状态数上限
+const DEFAULT_CACHE_CAPACITY: usize = 1024;
+/// 默认一次匹配中允许清空缓存的次数,超过后认为缓存在颠簸,退化为NFA模拟
+const DEFAULT_MAX_CACHE_CLEARS: usize = 8;
+
+/// 惰性DFA中缓存的一个状态
+struct LazyState {
+    /// 对应的NFA状态集(已求ε闭包并排序)
+    nfa_states: Vec<i32>,
+    /// 状态集中是否含有NFA的结束状态
+    is_match: bool,
+    /// 已经计算过的转换:输入字符 -> 缓存中的状态序号
+    transitions: HashMap<char, usize>,
+}
+
+/// 惰性DFA缓存的统计信息
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// 当前缓存的DFA状态数
+    pub cached_states: usize,
+    /// 累计清空缓存的次数
+    pub cache_clears: usize,
+    /// 累计退化为NFA模拟的次数
+    pub nfa_fallbacks: usize,
+}
+
+/// 一次匹配的结果
+struct RunResult {
+    /// 能被接受的最长前缀的字节长度
+    longest: Option<usize>,
+    /// 整个输入串是否被接受
+    full: bool,
+}
+
+/// **惰性DFA**:匹配时才由NFA的图按需计算DFA状态(子集构造法的move和ε-closure),
+/// 计算过的状态和转换放在有上限的缓存中,缓存满时清空重来,
+/// 一次匹配中清空次数过多时退化为NFA模拟
+pub struct LazyDfa<'a, C = LexemeCategory> {
+    nfa: &'a Graph<C>,
+    table: &'a CharSetTable,
+    /// 缓存的DFA状态数上限
+    cache_capacity: usize,
+    /// 一次匹配中允许清空缓存的次数
+    max_cache_clears: usize,
+    states: Vec<LazyState>,
+    /// NFA状态集 -> 缓存中的状态序号
+    state_map: HashMap<Vec<i32>, usize>,
+    stats: CacheStats,
+}
+
+impl<'a, C: Category> LazyDfa<'a, C> {
+    /// 构造函数,nfa中CHARSET边的driver_id在table中查找
+    pub fn new(nfa: &'a Graph<C>, table: &'a CharSetTable) -> LazyDfa<'a, C> {
+        LazyDfa {
+            nfa,
+            table,
+            cache_capacity: DEFAULT_CACHE_CAPACITY,
+            max_cache_clears: DEFAULT_MAX_CACHE_CLEARS,
+            states: Vec::new(),
+            state_map: HashMap::new(),
+            stats: CacheStats::default(),
+        }
+    }
+    /// 设置缓存的DFA状态数上限,至少为1
+    pub fn with_cache_capacity(mut self, cache_capacity: usize) -> Self {
+        self.cache_capacity = cache_capacity.max(1);
+        self
+    }
+    /// 设置一次匹配中允许清空缓存的次数
+    pub fn with_max_cache_clears(mut self, max_cache_clears: usize) -> Self {
+        self.max_cache_clears = max_cache_clears;
+        self
+    }
+    /// 缓存的统计信息
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            cached_states: self.states.len(),
+            ..self.stats.clone()
+        }
+    }
+    /// 整个输入串是否被接受
+    pub fn is_match(&mut self, input: &str) -> bool {
+        self.run(input).full
+    }
+    /// 能被接受的最长前缀的字节长度,没有能被接受的前缀时返回None
+    pub fn longest_match(&mut self, input: &str) -> Option<usize> {
+        self.run(input).longest
+    }
+
+    /// 清空缓存
+    fn clear_cache(&mut self) {
+        self.states.clear();
+        self.state_map.clear();
+        self.stats.cache_clears += 1;
+    }
+    /// 把一个NFA状态集作为新的DFA状态加入缓存,返回其序号
+    fn add_state(&mut self, nfa_states: Vec<i32>) -> usize {
+        let index = self.states.len();
+        let is_match = self.nfa.is_match_states(&nfa_states);
+        self.state_map.insert(nfa_states.clone(), index);
+        self.states.push(LazyState {
+            nfa_states,
+            is_match,
+            transitions: HashMap::new(),
+        });
+        index
+    }
+    /// 开始状态ε-closure(0)在缓存中的序号
+    fn start_state(&mut self) -> usize {
+        let nfa_states = self.nfa.epsilon_closure(&[0]);
+        if let Some(&index) = self.state_map.get(&nfa_states) {
+            return index;
+        }
+        if self.states.len() >= self.cache_capacity {
+            self.clear_cache();
+        }
+        self.add_state(nfa_states)
+    }
+    /// 求状态current经过字符c到达的状态,缓存需要清空但已超过允许的次数时返回None
+    fn next_state(&mut self, current: usize, c: char, clears: &mut usize) -> Option<usize> {
+        if let Some(&next) = self.states[current].transitions.get(&c) {
+            return Some(next);
+        }
+        // U := ε-closure(move(T, c))
+        let nfa_states = self.nfa.epsilon_closure(&self.nfa.move_by_char(
+            &self.states[current].nfa_states,
+            c,
+            self.table,
+        ));
+        if let Some(&next) = self.state_map.get(&nfa_states) {
+            self.states[current].transitions.insert(c, next);
+            return Some(next);
+        }
+        if self.states.len() >= self.cache_capacity {
+            if *clears >= self.max_cache_clears {
+                return None;
+            }
+            *clears += 1;
+            // 清空后current失效,只把新状态放入缓存
+            self.clear_cache();
+            return Some(self.add_state(nfa_states));
+        }
+        let next = self.add_state(nfa_states);
+        self.states[current].transitions.insert(c, next);
+        Some(next)
+    }
+    /// 从输入串的offset处开始,以NFA模拟的方式继续匹配
+    fn simulate_from(
+        &self,
+        mut nfa_states: Vec<i32>,
+        input: &str,
+        offset: usize,
+        mut longest: Option<usize>,
+    ) -> RunResult {
+        for (index, c) in input[offset..].char_indices() {
+            nfa_states =
+                self.nfa
+                    .epsilon_closure(&self.nfa.move_by_char(&nfa_states, c, self.table));
+            if nfa_states.is_empty() {
+                return RunResult {
+                    longest,
+                    full: false,
+                };
+            }
+            if self.nfa.is_match_states(&nfa_states) {
+                longest = Some(offset + index + c.len_utf8());
+            }
+        }
+        RunResult {
+            longest,
+            full: self.nfa.is_match_states(&nfa_states),
+        }
+    }
+    /// 从开始状态出发读入整个输入串,遇到死状态(空状态集)时提前结束
+    fn run(&mut self, input: &str) -> RunResult {
+        let mut clears = 0;
+        let mut current = self.start_state();
+        let mut longest = if self.states[current].is_match {
+            Some(0)
+        } else {
+            None
+        };
+        for (offset, c) in input.char_indices() {
+            current = match self.next_state(current, c, &mut clears) {
+                Some(next) => next,
+                // 缓存颠簸,从当前的NFA状态集开始退化为NFA模拟
+                None => {
+                    self.stats.nfa_fallbacks += 1;
+                    let nfa_states = self.states[current].nfa_states.clone();
+                    return self.simulate_from(nfa_states, input, offset, longest);
+                }
+            };
+            let state = &self.states[current];
+            if state.nfa_states.is_empty() {
+                return RunResult {
+                    longest,
+                    full: false,
+                };
+            }
+            if state.is_match {
+                longest = Some(offset + c.len_utf8());
+            }
+        }
+        RunResult {
+            longest,
+            full: self.states[current].is_match,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::DriverType;
+    use crate::test_util::all_strings;
+
+    /// (a|b)*abb
+    fn build_abb() -> Graph {
+        let a = Graph::generate_basic_nfa(DriverType::CHAR, 'a' as i32);
+        let b = Graph::generate_basic_nfa(DriverType::CHAR, 'b' as i32);
+        a.union(&b).closure().product(&a).product(&b).product(&b)
+    }
+
+    /// 惰性DFA与NFA模拟的结果一致
+    #[test]
+    fn test_is_match() {
+        let nfa = build_abb();
+        let table = CharSetTable::new();
+        let mut dfa = LazyDfa::new(&nfa, &table);
+        for s in all_strings(&['a', 'b'], 7) {
+            assert_eq!(dfa.is_match(&s), nfa.simulate(&s, &table), "{}", s);
+        }
+        assert!(dfa.is_match("abb"));
+        assert!(dfa.is_match("babaabb"));
+        assert!(!dfa.is_match("abba"));
+        // 子集构造法得到的(a|b)*abb的DFA共有5个状态
+        assert!(dfa.stats().cached_states <= 5);
+        assert_eq!(dfa.stats().cache_clears, 0);
+    }
+
+    /// 缓存很小时会清空缓存,颠簸时退化为NFA模拟,结果仍然正确
+    #[test]
+    fn test_cache_thrash() {
+        let nfa = build_abb();
+        let table = CharSetTable::new();
+        let mut dfa = LazyDfa::new(&nfa, &table)
+            .with_cache_capacity(2)
+            .with_max_cache_clears(1);
+        for s in all_strings(&['a', 'b'], 7) {
+            assert_eq!(dfa.is_match(&s), nfa.simulate(&s, &table), "{}", s);
+        }
+        let stats = dfa.stats();
+        assert!(stats.cached_states <= 2);
+        assert!(stats.cache_clears > 0);
+        assert!(stats.nfa_fallbacks > 0);
+    }
+
+    /// 字符集边上的最长匹配
+    #[test]
+    fn test_longest_match() {
+        let mut table = CharSetTable::new();
+        let digit = table.range('0', '9');
+        let nfa: Graph = Graph::generate_basic_nfa(DriverType::CHARSET, digit).plus_closure();
+        let mut dfa = LazyDfa::new(&nfa, &table);
+        assert_eq!(dfa.longest_match("123abc"), Some(3));
+        assert_eq!(dfa.longest_match("abc"), None);
+        assert_eq!(dfa.longest_match("٣"), None);
+        assert!(dfa.is_match("2024"));
+        assert!(!dfa.is_match(""));
+    }
+}
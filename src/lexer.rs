@@ -0,0 +1,657 @@
+use std::collections::VecDeque;
+use std::fmt::Display;
+
+use super::char_set::CharSetTable;
+use super::graph::{Category, DriverType, Edge, Graph, LexemeCategory, State, StateType};
+use super::pattern::{compile, PatternError};
+
+/// 初始的词法分析模式,不指定模式加入的规则属于该模式
+pub const INITIAL: &str = "INITIAL";
+
+/// 规则胜出后对模式栈的操作
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ModeAction {
+    /// 不改变模式
+    #[default]
+    Stay,
+    /// 进入指定的模式,之后可以用Pop回到当前模式
+    Push(String),
+    /// 回到进入当前模式之前的模式,已在栈底时不改变模式
+    Pop,
+    /// 把当前模式替换为指定的模式
+    Switch(String),
+}
+
+/// 规则胜出后接着运行的子扫描器,识别正则表达式不能描述的单词
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SubScanner {
+    /// 可嵌套的块注释:规则匹配到开始符号后,对开始与结束符号计数,直到嵌套深度回到0。
+    /// 单词包含规则匹配的部分、注释内容与最外层的结束符号
+    Nested { open: String, close: String },
+}
+
+/// 由规则构造词法分析器时的错误
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RuleError {
+    /// 规则的操作进入没有任何规则的模式
+    UnknownMode { rule: usize, mode: String },
+    /// 嵌套的子扫描器的开始或结束符号为空串
+    EmptyDelimiters { rule: usize },
+}
+
+impl Display for RuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleError::UnknownMode { rule, mode } => {
+                write!(
+                    f,
+                    "rule {} enters lexer mode {:?} which has no rules",
+                    rule, mode
+                )
+            }
+            RuleError::EmptyDelimiters { rule } => {
+                write!(f, "nested delimiters of rule {} must not be empty", rule)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+/// 一条词法规则:正则表达式编译得到的NFA及其种别
+#[derive(Clone, Debug)]
+pub struct Rule<C = LexemeCategory> {
+    pub category: C,
+    /// 规则的正则表达式,由NFA直接加入的规则为空串
+    pub pattern: String,
+    /// 规则的NFA,合并时其结束状态的种别取规则的种别
+    pub nfa: Graph<C>,
+    /// 规则所属的模式(start condition),只在这些模式下参与匹配
+    pub modes: Vec<String>,
+    pub action: ModeAction,
+    /// 规则胜出后运行的子扫描器
+    pub scanner: Option<SubScanner>,
+}
+
+/// 按优先级排列的一组词法规则,排在前面的规则优先:
+/// 同一个串被多条规则接受时,取最前面的规则的种别
+/// 种别可以是LexemeCategory以外实现了Category的类型
+#[derive(Clone, Debug)]
+pub struct RuleSet<C = LexemeCategory> {
+    pub rules: Vec<Rule<C>>,
+}
+
+impl<C> Default for RuleSet<C> {
+    fn default() -> Self {
+        RuleSet { rules: Vec::new() }
+    }
+}
+
+/// 合并所有规则得到的DFA
+#[derive(Clone, Debug)]
+pub struct LexerDfa<C = LexemeCategory> {
+    /// 结束状态的种别为其中胜出的规则的种别
+    pub dfa: Graph<C>,
+    /// DFA状态 -> 该状态接受的规则序号(升序),第一个为胜出的规则
+    pub accepting: Vec<Vec<usize>>,
+}
+
+/// 带模式的词法分析器:每个模式一个由该模式的规则合并得到的DFA
+#[derive(Clone, Debug)]
+pub struct Lexer<C = LexemeCategory> {
+    /// 模式名,modes[0]为INITIAL
+    pub modes: Vec<String>,
+    /// dfas[i]为模式modes[i]的DFA,其accepting中为规则在RuleSet中的序号
+    pub dfas: Vec<LexerDfa<C>>,
+    /// 每条规则胜出后对模式栈的操作
+    pub actions: Vec<ModeAction>,
+    /// 每条规则胜出后运行的子扫描器
+    pub scanners: Vec<Option<SubScanner>>,
+}
+
+impl<C> Lexer<C> {
+    /// 模式名对应的序号,没有该模式时返回None
+    pub fn mode_index(&self, mode: &str) -> Option<usize> {
+        self.modes.iter().position(|name| name == mode)
+    }
+}
+
+/// 规则在合并的DFA中的情况
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuleStatus {
+    /// 存在由该规则胜出的输入
+    Reachable,
+    /// 该规则接受的串都被优先级更高的规则接受,永远不会胜出
+    Shadowed,
+    /// 该规则不接受任何串
+    Empty,
+}
+
+/// 两条规则的冲突:example同时被两条规则接受,由优先级更高的winner胜出
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuleConflict {
+    pub winner: usize,
+    /// 最短的冲突输入
+    pub example: String,
+}
+
+/// 一条规则的分析结果
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuleReport<C = LexemeCategory> {
+    /// 规则的序号
+    pub rule: usize,
+    pub category: C,
+    pub status: RuleStatus,
+    /// 与优先级更高的规则的冲突,每条胜出的规则一项
+    pub conflicts: Vec<RuleConflict>,
+}
+
+impl<C: std::fmt::Debug> Display for RuleReport<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "rule {} ({:?}): ", self.rule, self.category)?;
+        f.write_str(match self.status {
+            RuleStatus::Reachable => "reachable",
+            RuleStatus::Shadowed => "shadowed",
+            RuleStatus::Empty => "matches nothing",
+        })?;
+        for conflict in self.conflicts.iter() {
+            write!(
+                f,
+                ", loses to rule {} on {:?}",
+                conflict.winner, conflict.example
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<C: Category> RuleSet<C> {
+    pub fn new() -> RuleSet<C> {
+        RuleSet { rules: Vec::new() }
+    }
+    /// 在INITIAL模式中加入一条优先级最低的规则,正则表达式中的字符集加入到table中,返回规则的序号
+    pub fn add(
+        &mut self,
+        category: C,
+        pattern: &str,
+        table: &mut CharSetTable,
+    ) -> Result<usize, PatternError> {
+        self.add_in(&[INITIAL], category, pattern, table)
+    }
+    /// 在指定的模式中加入一条优先级最低的规则,返回规则的序号
+    pub fn add_in(
+        &mut self,
+        modes: &[&str],
+        category: C,
+        pattern: &str,
+        table: &mut CharSetTable,
+    ) -> Result<usize, PatternError> {
+        let nfa = compile(pattern, table)?.with_category(category);
+        let rule = self.add_graph(category, nfa);
+        self.rules[rule].pattern = pattern.to_string();
+        self.rules[rule].modes = modes.iter().map(|mode| mode.to_string()).collect();
+        Ok(rule)
+    }
+    /// 由NFA直接在INITIAL模式中加入一条优先级最低的规则,返回规则的序号
+    pub fn add_graph(&mut self, category: C, nfa: Graph<C>) -> usize {
+        self.rules.push(Rule {
+            category,
+            pattern: String::new(),
+            nfa,
+            modes: vec![INITIAL.to_string()],
+            action: ModeAction::Stay,
+            scanner: None,
+        });
+        self.rules.len() - 1
+    }
+    /// 设置规则胜出后对模式栈的操作
+    pub fn set_action(&mut self, rule: usize, action: ModeAction) {
+        self.rules[rule].action = action;
+    }
+    /// 设置规则胜出后运行的子扫描器,只在由to_lexer得到的词法分析器中生效;
+    /// Nested的开始或结束符号为空串时返回EmptyDelimiters错误,不改变规则
+    pub fn set_scanner(&mut self, rule: usize, scanner: SubScanner) -> Result<(), RuleError> {
+        let SubScanner::Nested { open, close } = &scanner;
+        if open.is_empty() || close.is_empty() {
+            return Err(RuleError::EmptyDelimiters { rule });
+        }
+        self.rules[rule].scanner = Some(scanner);
+        Ok(())
+    }
+
+    /// 合并rules中的规则的NFA:新的开始状态经ε边到达各规则的开始状态,
+    /// 返回合并的NFA与每条规则的结束状态在其中的序号
+    fn combined_nfa(&self, rules: &[usize]) -> (Graph<C>, Vec<Vec<i32>>) {
+        let mut nfa = Graph {
+            graph_id: 0,
+            num_of_states: 0,
+            p_edge_table: Vec::new(),
+            p_state_table: vec![State {
+                state_id: 0,
+                state_type: StateType::UNMATCH,
+                category: C::empty(),
+            }],
+        };
+        let mut ends = Vec::with_capacity(rules.len());
+        for rule in rules.iter().map(|&rule| &self.rules[rule]) {
+            let offset = nfa.p_state_table.len() as i32;
+            let mut rule_ends = Vec::new();
+            for state in rule.nfa.p_state_table.iter() {
+                let is_match = state.state_type == StateType::MATCH;
+                if is_match {
+                    rule_ends.push(state.state_id + offset);
+                }
+                nfa.p_state_table.push(State {
+                    state_id: state.state_id + offset,
+                    state_type: state.state_type.clone(),
+                    category: if is_match { rule.category } else { C::empty() },
+                });
+            }
+            nfa.p_edge_table.push(Edge {
+                from_state: 0,
+                next_state: offset,
+                driver_id: -1,
+                driver_type: DriverType::NULL,
+            });
+            for edge in rule.nfa.p_edge_table.iter() {
+                nfa.p_edge_table.push(Edge {
+                    from_state: edge.from_state + offset,
+                    next_state: edge.next_state + offset,
+                    ..edge.clone()
+                });
+            }
+            ends.push(rule_ends);
+        }
+        nfa.num_of_states = nfa.p_state_table.len() as i32;
+        (nfa, ends)
+    }
+    /// 把所有规则(不区分模式)合并为一个DFA,字符集加入到table中
+    pub fn to_dfa(&self, table: &mut CharSetTable) -> LexerDfa<C> {
+        let rules: Vec<usize> = (0..self.rules.len()).collect();
+        self.rules_dfa(&rules, table)
+    }
+    /// 把属于模式mode的规则合并为一个DFA
+    pub fn mode_dfa(&self, mode: &str, table: &mut CharSetTable) -> LexerDfa<C> {
+        let rules: Vec<usize> = (0..self.rules.len())
+            .filter(|&rule| self.rules[rule].modes.iter().any(|name| name == mode))
+            .collect();
+        self.rules_dfa(&rules, table)
+    }
+    /// 为每个模式构造一个DFA,得到带模式的词法分析器。
+    /// 规则的操作中出现没有任何规则的模式时返回UnknownMode错误
+    pub fn to_lexer(&self, table: &mut CharSetTable) -> Result<Lexer<C>, RuleError> {
+        let mut modes = vec![INITIAL.to_string()];
+        for rule in self.rules.iter() {
+            for mode in rule.modes.iter() {
+                if !modes.contains(mode) {
+                    modes.push(mode.clone());
+                }
+            }
+        }
+        for (index, rule) in self.rules.iter().enumerate() {
+            if let ModeAction::Push(mode) | ModeAction::Switch(mode) = &rule.action {
+                if !modes.contains(mode) {
+                    return Err(RuleError::UnknownMode {
+                        rule: index,
+                        mode: mode.clone(),
+                    });
+                }
+            }
+        }
+        let dfas = modes
+            .iter()
+            .map(|mode| self.mode_dfa(mode, table))
+            .collect();
+        Ok(Lexer {
+            modes,
+            dfas,
+            actions: self.rules.iter().map(|rule| rule.action.clone()).collect(),
+            scanners: self.rules.iter().map(|rule| rule.scanner.clone()).collect(),
+        })
+    }
+    /// 把rules中的规则合并为一个DFA,accepting中为规则在self.rules中的序号
+    fn rules_dfa(&self, rules: &[usize], table: &mut CharSetTable) -> LexerDfa<C> {
+        let (nfa, ends) = self.combined_nfa(rules);
+        let (mut dfa, d_states) = nfa.subset_construction(table);
+        let accepting: Vec<Vec<usize>> = d_states
+            .iter()
+            .map(|state_vec| {
+                (0..ends.len())
+                    .filter(|&index| ends[index].iter().any(|end| state_vec.contains(end)))
+                    .map(|index| rules[index])
+                    .collect()
+            })
+            .collect();
+        for (state, rules) in dfa.p_state_table.iter_mut().zip(accepting.iter()) {
+            state.category = match rules.first() {
+                Some(&rule) => self.rules[rule].category,
+                None => C::empty(),
+            };
+        }
+        LexerDfa { dfa, accepting }
+    }
+    /// 分析每条规则能否在合并的DFA中胜出:某个DFA状态同时接受多条规则时,
+    /// 序号最小的规则胜出,其余规则与它冲突,冲突的示例输入是到达该状态的最短的串。
+    /// 一条规则不接受任何串时为Empty,接受的串都被更优先的规则接受时为Shadowed
+    pub fn analyze(&self, table: &mut CharSetTable) -> Vec<RuleReport<C>> {
+        let lexer_dfa = self.to_dfa(table);
+        let examples = shortest_inputs(&lexer_dfa.dfa, table);
+        let mut reports: Vec<RuleReport<C>> = self
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(rule, item)| RuleReport {
+                rule,
+                category: item.category,
+                status: RuleStatus::Empty,
+                conflicts: Vec::new(),
+            })
+            .collect();
+        // 按示例输入由短到长处理各状态,使每个冲突记录最短的示例
+        let mut states: Vec<usize> = (0..examples.len())
+            .filter(|&state| examples[state].is_some())
+            .collect();
+        states.sort_by_key(|&state| {
+            let example = examples[state].as_ref().unwrap();
+            (example.chars().count(), example.clone())
+        });
+        for state in states {
+            let rules = &lexer_dfa.accepting[state];
+            let winner = match rules.first() {
+                Some(&winner) => winner,
+                None => continue,
+            };
+            reports[winner].status = RuleStatus::Reachable;
+            for &rule in rules[1..].iter() {
+                let report = &mut reports[rule];
+                if report.status == RuleStatus::Empty {
+                    report.status = RuleStatus::Shadowed;
+                }
+                if report
+                    .conflicts
+                    .iter()
+                    .all(|conflict| conflict.winner != winner)
+                {
+                    report.conflicts.push(RuleConflict {
+                        winner,
+                        example: examples[state].clone().unwrap(),
+                    });
+                }
+            }
+        }
+        for report in reports.iter_mut() {
+            report.conflicts.sort_by_key(|conflict| conflict.winner);
+        }
+        reports
+    }
+}
+
+/// 从开始状态到达DFA每个状态的最短的串(广度优先,CHARSET边取最小的字符),
+/// 不可达的状态为None
+fn shortest_inputs<C: Category>(dfa: &Graph<C>, table: &CharSetTable) -> Vec<Option<String>> {
+    let mut result: Vec<Option<String>> = vec![None; dfa.p_state_table.len()];
+    let mut edges: Vec<(char, &Edge)> = dfa
+        .p_edge_table
+        .iter()
+        .filter_map(|edge| {
+            let c = match edge.driver_type {
+                DriverType::NULL => None,
+                DriverType::CHAR => char::from_u32(edge.driver_id as u32),
+                DriverType::CHARSET => table.ranges(edge.driver_id).first().map(|range| range.0),
+            };
+            c.map(|c| (c, edge))
+        })
+        .collect();
+    edges.sort_by_key(|&(c, _)| c);
+    result[0] = Some(String::new());
+    let mut queue: VecDeque<i32> = VecDeque::from([0]);
+    while let Some(state) = queue.pop_front() {
+        let input = result[state as usize].clone().unwrap();
+        for &(c, edge) in edges.iter() {
+            if edge.from_state == state && result[edge.next_state as usize].is_none() {
+                result[edge.next_state as usize] = Some(format!("{}{}", input, c));
+                queue.push_back(edge.next_state);
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::compile_with_set_operations;
+
+    fn rule_set(rules: &[(LexemeCategory, &str)], table: &mut CharSetTable) -> RuleSet {
+        let mut rule_set = RuleSet::new();
+        for &(category, pattern) in rules.iter() {
+            rule_set.add(category, pattern, table).unwrap();
+        }
+        rule_set
+    }
+
+    /// 合并的DFA中结束状态的种别取优先级最高的规则
+    #[test]
+    fn test_to_dfa() {
+        let mut table = CharSetTable::new();
+        let rules = rule_set(
+            &[
+                (LexemeCategory::LOGIC_OPERATOR, "and|or"),
+                (LexemeCategory::ID, "[a-z]+"),
+            ],
+            &mut table,
+        );
+        let lexer_dfa = rules.to_dfa(&mut table);
+        let category = |input: &str| {
+            let mut state = 0;
+            for c in input.chars() {
+                state = lexer_dfa.dfa.dfa_next(state, c, &table).unwrap();
+            }
+            lexer_dfa.dfa.p_state_table[state as usize].category
+        };
+        assert_eq!(category("and"), LexemeCategory::LOGIC_OPERATOR);
+        assert_eq!(category("an"), LexemeCategory::ID);
+        assert_eq!(category("android"), LexemeCategory::ID);
+        assert_eq!(category(""), LexemeCategory::EMPTY);
+    }
+
+    /// 被遮蔽的规则、不接受任何串的规则与冲突的示例输入
+    #[test]
+    fn test_analyze() {
+        let mut table = CharSetTable::new();
+        let mut rules = rule_set(
+            &[
+                (LexemeCategory::COMPARE_OPERATOR, "<=|<|=="),
+                (LexemeCategory::OPERATOR, "<<|<|\\+"),
+                (LexemeCategory::ID, "[a-z]+"),
+                (LexemeCategory::LOGIC_OPERATOR, "and|or"),
+                (LexemeCategory::INTEGER_CONST, "[0-9]+"),
+                (LexemeCategory::FLOAT_CONST, "0"),
+            ],
+            &mut table,
+        );
+        let empty = compile_with_set_operations("a&b", &mut table).unwrap();
+        rules.add_graph(LexemeCategory::NOTE, empty);
+        let reports = rules.analyze(&mut table);
+        let statuses: Vec<RuleStatus> = reports.iter().map(|report| report.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                RuleStatus::Reachable,
+                RuleStatus::Reachable,
+                RuleStatus::Reachable,
+                RuleStatus::Shadowed,
+                RuleStatus::Reachable,
+                RuleStatus::Shadowed,
+                RuleStatus::Empty,
+            ]
+        );
+        assert_eq!(
+            reports[1].conflicts,
+            vec![RuleConflict {
+                winner: 0,
+                example: "<".to_string()
+            }]
+        );
+        assert_eq!(
+            reports[3].conflicts,
+            vec![RuleConflict {
+                winner: 2,
+                example: "or".to_string()
+            }]
+        );
+        assert_eq!(
+            reports[3].to_string(),
+            "rule 3 (LOGIC_OPERATOR): shadowed, loses to rule 2 on \"or\""
+        );
+        assert_eq!(reports[5].conflicts[0].example, "0");
+        assert!(reports[6].conflicts.is_empty());
+    }
+
+    /// 每个模式一个DFA,只含该模式的规则
+    #[test]
+    fn test_to_lexer() {
+        let mut table = CharSetTable::new();
+        let mut rules = RuleSet::new();
+        rules.add(LexemeCategory::ID, "[a-z]+", &mut table).unwrap();
+        let open = rules
+            .add(LexemeCategory::OPERATOR, "/\\*", &mut table)
+            .unwrap();
+        rules.set_action(open, ModeAction::Push("COMMENT".to_string()));
+        let close = rules
+            .add_in(&["COMMENT"], LexemeCategory::NOTE, "\\*/", &mut table)
+            .unwrap();
+        rules.set_action(close, ModeAction::Pop);
+        rules
+            .add_in(
+                &["COMMENT", INITIAL],
+                LexemeCategory::SPACE_CONST,
+                " +",
+                &mut table,
+            )
+            .unwrap();
+        let lexer = rules.to_lexer(&mut table).unwrap();
+        assert_eq!(lexer.modes, vec![INITIAL, "COMMENT"]);
+        assert_eq!(lexer.mode_index("COMMENT"), Some(1));
+        assert_eq!(lexer.mode_index("STRING"), None);
+        let accepted = |mode: usize, input: &str| {
+            let dfa = &lexer.dfas[mode];
+            let mut state = 0;
+            for c in input.chars() {
+                match dfa.dfa.dfa_next(state, c, &table) {
+                    Some(next) => state = next,
+                    None => return None,
+                }
+            }
+            dfa.accepting[state as usize].first().copied()
+        };
+        assert_eq!(accepted(0, "abc"), Some(0));
+        assert_eq!(accepted(0, "*/"), None);
+        assert_eq!(accepted(1, "*/"), Some(close));
+        assert_eq!(accepted(1, "abc"), None);
+        assert_eq!(accepted(1, "  "), Some(3));
+        assert_eq!(accepted(0, "  "), Some(3));
+        assert_eq!(lexer.actions[open], ModeAction::Push("COMMENT".to_string()));
+    }
+
+    /// 进入没有规则的模式时出错
+    #[test]
+    fn test_unknown_mode() {
+        let mut table = CharSetTable::new();
+        let mut rules = RuleSet::new();
+        rules.add(LexemeCategory::ID, "a", &mut table).unwrap();
+        let rule = rules.add(LexemeCategory::ID, "b", &mut table).unwrap();
+        rules.set_action(rule, ModeAction::Switch("STRING".to_string()));
+        let error = rules.to_lexer(&mut table).unwrap_err();
+        assert_eq!(
+            error,
+            RuleError::UnknownMode {
+                rule: 1,
+                mode: "STRING".to_string()
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "rule 1 enters lexer mode \"STRING\" which has no rules"
+        );
+    }
+
+    /// 嵌套的开始或结束符号为空串时出错
+    #[test]
+    fn test_empty_delimiters() {
+        let mut table = CharSetTable::new();
+        let mut rules = RuleSet::new();
+        let rule = rules.add(LexemeCategory::NOTE, "#", &mut table).unwrap();
+        let nested = |open: &str, close: &str| SubScanner::Nested {
+            open: open.to_string(),
+            close: close.to_string(),
+        };
+        assert_eq!(
+            rules.set_scanner(rule, nested("", "#")),
+            Err(RuleError::EmptyDelimiters { rule })
+        );
+        assert_eq!(
+            rules
+                .set_scanner(rule, nested("#", ""))
+                .unwrap_err()
+                .to_string(),
+            "nested delimiters of rule 0 must not be empty"
+        );
+        assert_eq!(rules.rules[rule].scanner, None);
+        assert_eq!(rules.set_scanner(rule, nested("#", "#")), Ok(()));
+        assert_eq!(rules.rules[rule].scanner, Some(nested("#", "#")));
+    }
+
+    /// 自定义的种别类型
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    enum Kind {
+        Ident,
+        Arrow,
+        Minus,
+        None,
+    }
+
+    impl Category for Kind {
+        fn empty() -> Self {
+            Kind::None
+        }
+    }
+
+    /// 规则集合与分析结果使用自定义的种别
+    #[test]
+    fn test_custom_category() {
+        let mut table = CharSetTable::new();
+        let mut rules: RuleSet<Kind> = RuleSet::new();
+        rules.add(Kind::Ident, "[a-z]+", &mut table).unwrap();
+        rules.add(Kind::Arrow, "->", &mut table).unwrap();
+        rules.add(Kind::Minus, "-|->", &mut table).unwrap();
+        let lexer_dfa = rules.to_dfa(&mut table);
+        let arrow = lexer_dfa.dfa.dfa_next(0, '-', &table).unwrap();
+        let arrow = lexer_dfa.dfa.dfa_next(arrow, '>', &table).unwrap();
+        assert_eq!(
+            lexer_dfa.dfa.p_state_table[arrow as usize].category,
+            Kind::Arrow
+        );
+        assert_eq!(lexer_dfa.dfa.p_state_table[0].category, Kind::None);
+        let reports = rules.analyze(&mut table);
+        assert_eq!(reports[2].status, RuleStatus::Reachable);
+        assert_eq!(
+            reports[2].to_string(),
+            "rule 2 (Minus): reachable, loses to rule 1 on \"->\""
+        );
+
+        // 直接加入自定义种别的NFA
+        let digits = compile("[0-9]+", &mut table)
+            .unwrap()
+            .with_category(Kind::Ident);
+        assert_eq!(digits.p_state_table[0].category, Kind::None);
+        let rule = rules.add_graph(Kind::Arrow, digits);
+        let lexer_dfa = rules.to_dfa(&mut table);
+        let state = lexer_dfa.dfa.dfa_next(0, '7', &table).unwrap();
+        assert_eq!(lexer_dfa.accepting[state as usize], vec![rule]);
+        assert_eq!(
+            lexer_dfa.dfa.p_state_table[state as usize].category,
+            Kind::Arrow
+        );
+    }
+}
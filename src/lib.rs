@@ -1,3 +1,26 @@
+pub mod approximate;
+pub mod ast;
 pub mod char_set;
+pub mod count;
+pub mod decode;
+pub mod derivative;
+pub mod equivalence;
+pub mod generate;
 pub mod graph;
-pub mod regular_expression;
\ No newline at end of file
+pub mod keyword;
+pub mod lazy_dfa;
+pub mod lexer;
+pub mod pattern;
+pub mod position;
+pub mod product;
+pub mod regular_expression;
+pub mod rule_library;
+pub mod span;
+pub mod state_elimination;
+#[cfg(test)]
+mod test_util;
+pub mod tokenizer;
+pub mod trivia;
+#[rustfmt::skip]
+pub mod unicode_tables;
+pub mod utf8;
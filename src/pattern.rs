@@ -0,0 +1,868 @@
+use std::fmt::Display;
+
+use super::ast::{AssertionKind, Ast};
+use super::char_set::{
+    case_fold_ranges, complement_ranges, lookup_unicode_property, normalize_ranges, CharSetTable,
+    PredefinedClass,
+};
+use super::graph::Graph;
+
+/// 正则表达式的语法选项
+#[derive(Clone, Debug)]
+pub struct PatternOptions {
+    /// 为true时`\d`、`\w`、`\s`、`[:alpha:]`等预定义字符类按Unicode定义,否则只含ASCII字符
+    pub unicode: bool,
+    /// 为true时整个正则表达式大小写不敏感,相当于在开头写`(?i)`
+    pub case_insensitive: bool,
+    /// 为true时编译为在UTF-8字节上运行的自动机,见`Graph::to_utf8_graph`
+    pub bytes: bool,
+    /// 为true时`&`(交)与`~`(补)是元字符,否则是普通字符;两种情况下`\&`、`\~`都表示字符本身
+    pub set_operations: bool,
+}
+
+impl Default for PatternOptions {
+    fn default() -> Self {
+        PatternOptions {
+            unicode: true,
+            case_insensitive: false,
+            bytes: false,
+            set_operations: false,
+        }
+    }
+}
+
+/// 正则表达式的语法错误
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatternError {
+    /// 出错位置在正则表达式中的字节偏移
+    pub position: usize,
+    pub message: String,
+}
+
+impl Display for PatternError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "pattern error at {}: {}", self.position, self.message)
+    }
+}
+
+/// 把正则表达式编译为NFA,其中的字符集加入到table中
+///
+/// 支持的语法:`|`、连接、`*`、`+`、`?`、`{n}`、`{n,}`、`{n,m}`、`(...)`、`.`、
+/// `[...]`、`[^...]`、`[:alpha:]`、`[:^alpha:]`、`\d`、`\w`、`\s`、`\D`、`\W`、`\S`、
+/// `\p{..}`、`\P{..}`、`\n`、`\t`、`\x{..}`等转义,标志`(?i)`、`(?-i)`、`(?i:...)`,
+/// 以及正则表达式开头的`^`和结尾的`$`;交`&`与补`~`需要打开`PatternOptions::set_operations`
+pub fn compile(pattern: &str, table: &mut CharSetTable) -> Result<Graph, PatternError> {
+    compile_with_options(pattern, table, &PatternOptions::default())
+}
+
+/// 按给定的语法选项把正则表达式编译为NFA:先分析为抽象语法树,化简后再生成NFA
+pub fn compile_with_options(
+    pattern: &str,
+    table: &mut CharSetTable,
+    options: &PatternOptions,
+) -> Result<Graph, PatternError> {
+    let (ast, unsupported) = parse_pattern(pattern, options)?;
+    if let Some(error) = unsupported {
+        return Err(error);
+    }
+    let graph = ast.simplify().to_graph(table)?;
+    if options.bytes {
+        Ok(graph.to_utf8_graph(table))
+    } else {
+        Ok(graph)
+    }
+}
+
+/// 把正则表达式分析为抽象语法树,大小写不敏感的部分已展开为字符类
+pub fn parse(pattern: &str, options: &PatternOptions) -> Result<Ast, PatternError> {
+    parse_pattern(pattern, options).map(|(ast, _)| ast)
+}
+
+/// 分析正则表达式,同时返回第一个无法生成NFA的断言的错误
+fn parse_pattern(
+    pattern: &str,
+    options: &PatternOptions,
+) -> Result<(Ast, Option<PatternError>), PatternError> {
+    let mut parser = Parser {
+        pattern,
+        chars: pattern.char_indices().collect(),
+        pos: 0,
+        depth: 0,
+        options: options.clone(),
+        case_insensitive: options.case_insensitive,
+        unsupported: None,
+    };
+    let ast = parser.parse_alternation()?;
+    match parser.peek() {
+        None => Ok((ast, parser.unsupported)),
+        Some(')') => Err(parser.error("unmatched ')'")),
+        Some(c) => Err(parser.error(&format!("unexpected '{}'", c))),
+    }
+}
+
+/// 递归下降的语法分析器,生成抽象语法树
+struct Parser<'a> {
+    pattern: &'a str,
+    chars: Vec<(usize, char)>,
+    /// 当前字符在chars中的序号
+    pos: usize,
+    /// 括号的嵌套深度
+    depth: usize,
+    options: PatternOptions,
+    /// 当前是否大小写不敏感,由(?i)打开、(?-i)关闭,作用到所在括号的结尾
+    case_insensitive: bool,
+    /// 第一个无法生成NFA的断言:不在最外层选择项开头的`^`、不在结尾的`$`以及`\b`、`\B`
+    unsupported: Option<PatternError>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).map(|&(_, c)| c)
+    }
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        if c.is_some() {
+            self.pos += 1;
+        }
+        c
+    }
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+    /// 当前位置的字节偏移
+    fn offset(&self) -> usize {
+        match self.chars.get(self.pos) {
+            Some(&(offset, _)) => offset,
+            None => self.pattern.len(),
+        }
+    }
+    fn error(&self, message: &str) -> PatternError {
+        PatternError {
+            position: self.offset(),
+            message: message.to_string(),
+        }
+    }
+
+    /// 选择: intersection ('|' intersection)*
+    fn parse_alternation(&mut self) -> Result<Ast, PatternError> {
+        let mut items = vec![self.parse_intersection()?];
+        while self.eat('|') {
+            items.push(self.parse_intersection()?);
+        }
+        Ok(if items.len() == 1 {
+            items.pop().unwrap()
+        } else {
+            Ast::Alternation(items)
+        })
+    }
+    /// 交: concat ('&' concat)*,只在打开set_operations时分析
+    fn parse_intersection(&mut self) -> Result<Ast, PatternError> {
+        let mut items = vec![self.parse_concat()?];
+        while self.is_set_operator('&') {
+            // 交的两侧都不能为空,`a&&b`多半是把`&&`当作了普通字符
+            if items.last() == Some(&Ast::Empty) {
+                return Err(self.error("intersection operator missing expression"));
+            }
+            self.next();
+            items.push(self.parse_concat()?);
+            if items.last() == Some(&Ast::Empty) {
+                return Err(self.error("intersection operator missing expression"));
+            }
+        }
+        Ok(if items.len() == 1 {
+            items.pop().unwrap()
+        } else {
+            Ast::Intersection(items)
+        })
+    }
+    /// 连接: complement*
+    fn parse_concat(&mut self) -> Result<Ast, PatternError> {
+        let mut items: Vec<Ast> = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' || self.is_set_operator('&') {
+                break;
+            }
+            // 单独的标志(?i)改变后续部分的选项,本身不对应语法树的结点
+            if self.parse_flags_only()? {
+                continue;
+            }
+            if c == '^' && (self.depth > 0 || !items.is_empty()) {
+                self.record_unsupported("'^' is only supported at the start of the pattern");
+            }
+            let item = self.parse_complement()?;
+            if item == Ast::Assertion(AssertionKind::EndText)
+                && (self.depth > 0 || !matches!(self.peek(), None | Some('|')))
+            {
+                self.pos -= 1;
+                self.record_unsupported("'$' is only supported at the end of the pattern");
+                self.pos += 1;
+            }
+            items.push(item);
+        }
+        Ok(match items.len() {
+            // 空串
+            0 => Ast::Empty,
+            1 => items.pop().unwrap(),
+            _ => Ast::Concat(items),
+        })
+    }
+    /// 记录第一个无法生成NFA的断言
+    fn record_unsupported(&mut self, message: &str) {
+        if self.unsupported.is_none() {
+            self.unsupported = Some(self.error(message));
+        }
+    }
+    /// 当前字符是c且c作为交或补的运算符
+    fn is_set_operator(&self, c: char) -> bool {
+        self.options.set_operations && self.peek() == Some(c)
+    }
+    /// 补: '~' complement | repeat,`~`作用于其后的整个重复,如`~a*`表示`~(a*)`
+    fn parse_complement(&mut self) -> Result<Ast, PatternError> {
+        if !self.is_set_operator('~') {
+            return self.parse_repeat();
+        }
+        self.next();
+        if matches!(self.peek(), None | Some('|' | ')')) || self.is_set_operator('&') {
+            return Err(self.error("complement operator missing expression"));
+        }
+        Ok(Ast::Complement(Box::new(self.parse_complement()?)))
+    }
+    /// 重复: atom ('*' | '+' | '?' | '{n}' | '{n,}' | '{n,m}')*
+    fn parse_repeat(&mut self) -> Result<Ast, PatternError> {
+        let mut ast = self.parse_atom()?;
+        loop {
+            let (min, max) = match self.peek() {
+                Some('*') => (0, None),
+                Some('+') => (1, None),
+                Some('?') => (0, Some(1)),
+                Some('{') => {
+                    let start = self.pos;
+                    match self.parse_counted()? {
+                        Some((min, max)) => {
+                            ast = Ast::repeat(ast, min, max);
+                            continue;
+                        }
+                        // 不是计数重复,'{'作为普通字符
+                        None => {
+                            self.pos = start;
+                            return Ok(ast);
+                        }
+                    }
+                }
+                _ => return Ok(ast),
+            };
+            ast = Ast::repeat(ast, min, max);
+            self.pos += 1;
+        }
+    }
+    /// 计数重复{n}、{n,}、{n,m},格式不符时返回None
+    fn parse_counted(&mut self) -> Result<Option<(u32, Option<u32>)>, PatternError> {
+        let start = self.offset();
+        self.next();
+        let min = match self.parse_number() {
+            Some(min) => min,
+            None => return Ok(None),
+        };
+        let max = if self.eat(',') {
+            if self.peek() == Some('}') {
+                None
+            } else {
+                match self.parse_number() {
+                    Some(max) => Some(max),
+                    None => return Ok(None),
+                }
+            }
+        } else {
+            Some(min)
+        };
+        if !self.eat('}') {
+            return Ok(None);
+        }
+        if matches!(max, Some(max) if max < min) {
+            return Err(PatternError {
+                position: start,
+                message: "invalid repetition range".to_string(),
+            });
+        }
+        Ok(Some((min, max)))
+    }
+    fn parse_number(&mut self) -> Option<u32> {
+        let mut value: Option<u32> = None;
+        while let Some(digit) = self.peek().and_then(|c| c.to_digit(10)) {
+            value = Some(value.unwrap_or(0).checked_mul(10)?.checked_add(digit)?);
+            self.pos += 1;
+        }
+        value
+    }
+    /// 原子:字符、转义、字符类、'.'、断言或括号
+    fn parse_atom(&mut self) -> Result<Ast, PatternError> {
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return Err(self.error("unexpected end of pattern")),
+        };
+        match c {
+            '(' => {
+                self.next();
+                // 括号内的标志只作用到括号结尾
+                let case_insensitive = self.case_insensitive;
+                // (?flags:...)是非捕获括号,不生成Group结点
+                let group = !self.eat('?');
+                if !group {
+                    self.parse_flags()?;
+                    if !self.eat(':') {
+                        return Err(self.error("expected ':' after flags"));
+                    }
+                }
+                self.depth += 1;
+                let ast = self.parse_alternation()?;
+                self.depth -= 1;
+                if !self.eat(')') {
+                    return Err(self.error("missing ')'"));
+                }
+                self.case_insensitive = case_insensitive;
+                Ok(if group {
+                    Ast::Group(Box::new(ast))
+                } else {
+                    ast
+                })
+            }
+            '*' | '+' | '?' => Err(self.error("repetition operator missing expression")),
+            '^' | '$' => {
+                self.next();
+                Ok(Ast::Assertion(if c == '^' {
+                    AssertionKind::StartText
+                } else {
+                    AssertionKind::EndText
+                }))
+            }
+            '[' => {
+                let ranges = self.parse_bracket()?;
+                self.class_ast(ranges)
+            }
+            '.' => {
+                self.next();
+                self.class_ast(complement_ranges(&[('\n', '\n')]))
+            }
+            '\\' => {
+                let kind = match self.chars.get(self.pos + 1) {
+                    Some(&(_, 'b')) => Some(AssertionKind::WordBoundary),
+                    Some(&(_, 'B')) => Some(AssertionKind::NotWordBoundary),
+                    _ => None,
+                };
+                if let Some(kind) = kind {
+                    self.record_unsupported("word boundary assertions are not supported");
+                    self.pos += 2;
+                    return Ok(Ast::Assertion(kind));
+                }
+                match self.parse_escape()? {
+                    Escaped::Char(c) => Ok(self.char_ast(c)),
+                    Escaped::Class(ranges) => self.class_ast(ranges),
+                }
+            }
+            _ => {
+                self.next();
+                Ok(self.char_ast(c))
+            }
+        }
+    }
+    /// 标志:'i'打开大小写不敏感,'-'之后的标志为关闭;
+    /// 没有标志的(?)以及'-'之后没有标志时出错,(?:...)不带标志是允许的
+    fn parse_flags(&mut self) -> Result<(), PatternError> {
+        let mut enable = true;
+        // 当前的'-'之前或之后已有的标志数
+        let mut flags = 0;
+        loop {
+            match self.peek() {
+                Some('i') => {
+                    self.case_insensitive = enable;
+                    flags += 1;
+                }
+                Some('-') if enable => {
+                    enable = false;
+                    flags = 0;
+                }
+                Some(':' | ')') if !enable && flags == 0 => {
+                    return Err(self.error("missing flag after '-'"))
+                }
+                Some(')') if flags == 0 => return Err(self.error("empty flag group")),
+                Some(':') | Some(')') => return Ok(()),
+                Some(c) => return Err(self.error(&format!("unknown flag '{}'", c))),
+                None => return Err(self.error("missing ')'")),
+            }
+            self.pos += 1;
+        }
+    }
+    /// 单独的标志(?i)、(?-i),不是时不移动位置并返回false
+    fn parse_flags_only(&mut self) -> Result<bool, PatternError> {
+        let start = self.pos;
+        if !(self.eat('(') && self.eat('?')) {
+            self.pos = start;
+            return Ok(false);
+        }
+        // 先试探到')'或':'为止,(?i:...)交给parse_atom处理
+        let case_insensitive = self.case_insensitive;
+        self.parse_flags()?;
+        if self.eat(')') {
+            return Ok(true);
+        }
+        self.case_insensitive = case_insensitive;
+        self.pos = start;
+        Ok(false)
+    }
+    /// 单个字符,大小写不敏感时换成其大小写的等价类
+    fn char_ast(&mut self, c: char) -> Ast {
+        if self.case_insensitive {
+            let ranges = case_fold_ranges(&[(c, c)]);
+            if ranges.len() > 1 || ranges[0].0 != ranges[0].1 {
+                return Ast::Class(ranges);
+            }
+        }
+        Ast::Literal(c)
+    }
+    /// 字符类的补集,大小写不敏感时先折叠再取补,使补集不含被排除的字符的大小写形式
+    fn complement_class(&self, ranges: &[(char, char)]) -> Vec<(char, char)> {
+        if self.case_insensitive {
+            complement_ranges(&case_fold_ranges(ranges))
+        } else {
+            complement_ranges(ranges)
+        }
+    }
+    /// 字符类,大小写不敏感时做大小写折叠;空的字符类(如`[^\s\S]`)表示空语言
+    fn class_ast(&mut self, ranges: Vec<(char, char)>) -> Result<Ast, PatternError> {
+        if self.case_insensitive {
+            return Ok(Ast::Class(case_fold_ranges(&ranges)));
+        }
+        Ok(Ast::class(&ranges))
+    }
+    /// 方括号字符类[...]
+    fn parse_bracket(&mut self) -> Result<Vec<(char, char)>, PatternError> {
+        let start = self.offset();
+        self.next();
+        let negated = self.eat('^');
+        let mut ranges: Vec<(char, char)> = Vec::new();
+        let mut first = true;
+        loop {
+            let c = match self.peek() {
+                Some(c) => c,
+                None => {
+                    return Err(PatternError {
+                        position: start,
+                        message: "unclosed character class".to_string(),
+                    })
+                }
+            };
+            // 第一个']'作为普通字符
+            if c == ']' && !first {
+                self.next();
+                break;
+            }
+            first = false;
+            if c == '[' && self.chars.get(self.pos + 1).map(|&(_, c)| c) == Some(':') {
+                ranges.extend(self.parse_posix_class()?);
+                continue;
+            }
+            let from_char = match self.parse_class_item()? {
+                Escaped::Char(c) => c,
+                Escaped::Class(class) => {
+                    ranges.extend(class);
+                    continue;
+                }
+            };
+            // 字符范围a-z,末尾的'-'作为普通字符
+            let is_range = self.peek() == Some('-')
+                && !matches!(self.chars.get(self.pos + 1), Some(&(_, ']')) | None);
+            if !is_range {
+                ranges.push((from_char, from_char));
+                continue;
+            }
+            self.next();
+            let to_char = match self.parse_class_item()? {
+                Escaped::Char(c) => c,
+                Escaped::Class(_) => return Err(self.error("invalid range end")),
+            };
+            if from_char > to_char {
+                return Err(self.error("invalid range: start is greater than end"));
+            }
+            ranges.push((from_char, to_char));
+        }
+        normalize_ranges(&mut ranges);
+        if negated {
+            ranges = self.complement_class(&ranges);
+        }
+        Ok(ranges)
+    }
+    /// 字符类中的一项:字符或转义
+    fn parse_class_item(&mut self) -> Result<Escaped, PatternError> {
+        if self.peek() == Some('\\') {
+            return self.parse_escape();
+        }
+        match self.next() {
+            Some(c) => Ok(Escaped::Char(c)),
+            None => Err(self.error("unclosed character class")),
+        }
+    }
+    /// POSIX字符类[:alpha:]、[:^alpha:]
+    fn parse_posix_class(&mut self) -> Result<Vec<(char, char)>, PatternError> {
+        let start = self.offset();
+        self.pos += 2;
+        let negated = self.eat('^');
+        let mut name = String::new();
+        while let Some(c) = self.next() {
+            if c == ':' {
+                break;
+            }
+            name.push(c);
+        }
+        if !self.eat(']') {
+            return Err(PatternError {
+                position: start,
+                message: "unclosed POSIX class".to_string(),
+            });
+        }
+        let class = match PredefinedClass::from_posix_name(&name) {
+            Some(class) => class,
+            None => {
+                return Err(PatternError {
+                    position: start,
+                    message: format!("unknown POSIX class '{}'", name),
+                })
+            }
+        };
+        let ranges = class.ranges(self.options.unicode);
+        Ok(if negated {
+            self.complement_class(&ranges)
+        } else {
+            ranges
+        })
+    }
+    /// 转义序列
+    fn parse_escape(&mut self) -> Result<Escaped, PatternError> {
+        let start = self.offset();
+        self.next();
+        let c = match self.next() {
+            Some(c) => c,
+            None => return Err(self.error("incomplete escape sequence")),
+        };
+        let unicode = self.options.unicode;
+        let escaped = match c {
+            'd' => Escaped::Class(PredefinedClass::Digit.ranges(unicode)),
+            'w' => Escaped::Class(PredefinedClass::Word.ranges(unicode)),
+            's' => Escaped::Class(PredefinedClass::Space.ranges(unicode)),
+            'D' => Escaped::Class(self.complement_class(&PredefinedClass::Digit.ranges(unicode))),
+            'W' => Escaped::Class(self.complement_class(&PredefinedClass::Word.ranges(unicode))),
+            'S' => Escaped::Class(self.complement_class(&PredefinedClass::Space.ranges(unicode))),
+            'p' | 'P' => {
+                let name = if self.eat('{') {
+                    let mut name = String::new();
+                    loop {
+                        match self.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(self.error("unclosed property name")),
+                        }
+                    }
+                    name
+                } else {
+                    match self.next() {
+                        Some(c) => c.to_string(),
+                        None => return Err(self.error("missing property name")),
+                    }
+                };
+                let ranges = match lookup_unicode_property(&name) {
+                    Some(ranges) => ranges.to_vec(),
+                    None => {
+                        return Err(PatternError {
+                            position: start,
+                            message: format!("unknown unicode property '{}'", name),
+                        })
+                    }
+                };
+                Escaped::Class(if c == 'P' {
+                    self.complement_class(&ranges)
+                } else {
+                    ranges
+                })
+            }
+            'n' => Escaped::Char('\n'),
+            't' => Escaped::Char('\t'),
+            'r' => Escaped::Char('\r'),
+            'f' => Escaped::Char('\u{C}'),
+            'v' => Escaped::Char('\u{B}'),
+            '0' => Escaped::Char('\0'),
+            'x' => Escaped::Char(self.parse_hex(start)?),
+            c if c.is_alphanumeric() => {
+                return Err(PatternError {
+                    position: start,
+                    message: format!("unknown escape '\\{}'", c),
+                })
+            }
+            c => Escaped::Char(c),
+        };
+        Ok(escaped)
+    }
+    /// \xHH或\x{H...}
+    fn parse_hex(&mut self, start: usize) -> Result<char, PatternError> {
+        let mut digits = String::new();
+        if self.eat('{') {
+            loop {
+                match self.next() {
+                    Some('}') => break,
+                    Some(c) => digits.push(c),
+                    None => return Err(self.error("unclosed hex escape")),
+                }
+            }
+        } else {
+            for _ in 0..2 {
+                if let Some(c) = self.next() {
+                    digits.push(c);
+                }
+            }
+        }
+        // from_str_radix允许开头的'+',先检查每个字符都是十六进制数字
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(PatternError {
+                position: start,
+                message: format!("invalid hex escape '{}'", digits),
+            });
+        }
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or(PatternError {
+                position: start,
+                message: format!("invalid hex escape '{}'", digits),
+            })
+    }
+}
+
+/// 转义序列的结果
+enum Escaped {
+    Char(char),
+    Class(Vec<(char, char)>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::compile_with_set_operations;
+
+    fn is_match(pattern: &str, input: &str) -> bool {
+        let mut table = CharSetTable::new();
+        let graph = compile(pattern, &mut table).unwrap();
+        graph.simulate(input, &table)
+    }
+
+    /// 选择、连接、闭包和计数重复
+    #[test]
+    fn test_operators() {
+        assert!(is_match("(a|b)*abb", "aababb"));
+        assert!(!is_match("(a|b)*abb", "abab"));
+        assert!(is_match("ab+c?", "abbb"));
+        assert!(!is_match("ab+c?", "ac"));
+        assert!(is_match("a{2,3}", "aaa"));
+        assert!(!is_match("a{2,3}", "aaaa"));
+        assert!(is_match("a{2,}", "aaaaa"));
+        assert!(is_match("a{}", "a{}"));
+        assert!(is_match("x|", ""));
+        assert!(is_match("\\(\\x41\\x{4e2d}\\)", "(A中)"));
+        assert!(is_match("^ab$|^c", "ab"));
+        assert!(is_match("^ab$|^c", "c"));
+    }
+
+    /// 方括号字符类与'.'
+    #[test]
+    fn test_bracket() {
+        assert!(is_match("[a-c_]+", "ab_c"));
+        assert!(!is_match("[a-c_]+", "abd"));
+        assert!(is_match("[^a-c]", "d"));
+        assert!(!is_match("[^a-c]", "b"));
+        assert!(is_match("[]a-]+", "]-a"));
+        assert!(is_match(".", "中"));
+        assert!(!is_match(".", "\n"));
+        // 空的字符类不接受任何字符
+        assert!(!is_match("[^\\s\\S]", "a"));
+        assert!(!is_match("[^\\s\\S]", ""));
+        assert!(is_match("[^\\s\\S]*", ""));
+    }
+
+    /// \d \w \s \D \W \S及其ASCII与Unicode两种定义
+    #[test]
+    fn test_shorthand() {
+        assert!(is_match("\\d+", "0123٣"));
+        assert!(is_match("\\w+", "snake_case_变量"));
+        assert!(is_match("\\s", "\u{3000}"));
+        assert!(is_match("\\D\\W\\S", "a-b"));
+        assert!(!is_match("\\D", "5"));
+        assert!(is_match("[\\d\\s]+", "1 2"));
+        assert!(is_match("[^\\W\\d]+", "abc"));
+        assert!(!is_match("[^\\W\\d]+", "a1"));
+
+        let options = PatternOptions {
+            unicode: false,
+            ..PatternOptions::default()
+        };
+        let mut table = CharSetTable::new();
+        let graph = compile_with_options("\\w+", &mut table, &options).unwrap();
+        assert!(graph.simulate("snake_case", &table));
+        assert!(!graph.simulate("变量", &table));
+        let graph = compile_with_options("\\D", &mut table, &options).unwrap();
+        assert!(graph.simulate("٣", &table));
+    }
+
+    /// POSIX字符类与Unicode属性
+    #[test]
+    fn test_posix_and_property() {
+        assert!(is_match("[[:alpha:]_][[:alnum:]_]*", "_id42"));
+        assert!(!is_match("[[:alpha:]_][[:alnum:]_]*", "4id"));
+        assert!(is_match("[[:^digit:]]", "x"));
+        assert!(!is_match("[[:^digit:]]", "7"));
+        assert!(is_match("[[:xdigit:]]+", "DeadBeef"));
+        assert!(is_match("[[:punct:]]", "。"));
+        assert!(is_match("\\p{L}\\p{Nd}", "λ٣"));
+        assert!(is_match("\\pL", "x"));
+        assert!(is_match("\\P{Greek}", "x"));
+        assert!(!is_match("\\P{Greek}", "λ"));
+    }
+
+    /// 大小写不敏感:(?i)标志与PatternOptions
+    #[test]
+    fn test_case_insensitive() {
+        assert!(is_match("(?i)select", "SeLeCt"));
+        assert!(is_match("(?i)[a-c]+", "AbC"));
+        assert!(is_match("(?i)σ+", "Σςσ"));
+        assert!(is_match("(?i)k", "\u{212A}"));
+        assert!(is_match("a(?i:b)c", "aBc"));
+        assert!(!is_match("a(?i:b)c", "aBC"));
+        assert!(is_match("(?i)a(?-i)b", "Ab"));
+        assert!(!is_match("(?i)a(?-i)b", "AB"));
+        assert!(is_match("(a(?i)b)c|d", "aBc"));
+        assert!(!is_match("(a(?i)b)c|d", "aBC"));
+        // 取补之前先折叠,补集不含被排除的字符的大小写形式
+        assert!(!is_match("(?i)[^a]", "a"));
+        assert!(!is_match("(?i)[^a]", "A"));
+        assert!(is_match("(?i)[^a]", "b"));
+        assert!(!is_match("(?i)[^k]", "\u{212A}"));
+        assert!(!is_match("(?i)[^[:^alpha:]]", "1"));
+        assert!(!is_match("(?i)[[:^lower:]]", "A"));
+        assert!(!is_match("(?i)\\P{Lu}", "A"));
+        assert!(!is_match("(?i)\\P{Lu}", "a"));
+        assert!(is_match("(?i)\\P{Lu}", "1"));
+        assert!(is_match("[^a]", "A"));
+
+        let options = PatternOptions {
+            case_insensitive: true,
+            ..PatternOptions::default()
+        };
+        let mut table = CharSetTable::new();
+        let graph = compile_with_options("where|\\p{Lu}", &mut table, &options).unwrap();
+        assert!(graph.simulate("WHERE", &table));
+        assert!(graph.simulate("x", &table));
+        let graph = compile_with_options("(?-i)where", &mut table, &options).unwrap();
+        assert!(!graph.simulate("WHERE", &table));
+    }
+
+    /// 编译为UTF-8字节上的自动机
+    #[test]
+    fn test_bytes() {
+        let options = PatternOptions {
+            bytes: true,
+            ..PatternOptions::default()
+        };
+        let mut table = CharSetTable::new();
+        let graph = compile_with_options("\\p{Han}+\\d", &mut table, &options).unwrap();
+        assert!(graph.simulate_bytes("汉字3".as_bytes(), &table));
+        assert!(!graph.simulate_bytes(b"\xE6\xB1", &table));
+    }
+
+    /// 交与补,优先级从低到高为`|`、`&`、连接、`~`、重复
+    #[test]
+    fn test_intersection_and_complement() {
+        let is_match = |pattern: &str, input: &str| {
+            let mut table = CharSetTable::new();
+            let graph = compile_with_set_operations(pattern, &mut table).unwrap();
+            graph.simulate(input, &table)
+        };
+        let id_not_keyword = "[a-z]+&~(if|else)";
+        assert!(is_match(id_not_keyword, "iff"));
+        assert!(!is_match(id_not_keyword, "else"));
+        assert!(!is_match(id_not_keyword, "A"));
+        assert!(is_match("a.*&.*b|c", "c"));
+        assert!(is_match("a.*&.*b|c", "axb"));
+        assert!(!is_match("a.*&.*b|c", "ax"));
+        // 不含"*/"的串
+        assert!(is_match("/\\*~(.*\\*/.*)\\*/", "/* a * b */"));
+        assert!(!is_match("/\\*~(.*\\*/.*)\\*/", "/* a */ b */"));
+        assert!(is_match("~a*", "b"));
+        assert!(!is_match("~a*", "aa"));
+        assert!(is_match("x(~y)z", "xz"));
+        assert!(is_match("\\&\\~", "&~"));
+    }
+
+    /// 没有打开set_operations时`&`与`~`是普通字符,打开后`&&`报错
+    #[test]
+    fn test_set_operations_option() {
+        assert!(is_match("a&&b|~", "a&&b"));
+        assert!(is_match("a&&b|~", "~"));
+        assert!(is_match("[a-z]+&~x", "if&~x"));
+        assert!(is_match("\\&\\~", "&~"));
+        let mut table = CharSetTable::new();
+        for (pattern, position) in [("a&&b", 2), ("&a", 0), ("a&", 2), ("(a&)", 3)] {
+            let error = compile_with_set_operations(pattern, &mut table).unwrap_err();
+            assert_eq!(
+                error,
+                PatternError {
+                    position,
+                    message: "intersection operator missing expression".to_string(),
+                },
+                "{}",
+                pattern
+            );
+        }
+    }
+
+    /// 语法错误及其位置
+    #[test]
+    fn test_errors() {
+        let mut table = CharSetTable::new();
+        let cases = [
+            ("(ab", 3),
+            ("ab)", 2),
+            ("*a", 0),
+            ("[a-", 0),
+            ("[z-a]", 4),
+            ("[[:alfa:]]", 1),
+            ("\\p{Klingon}", 0),
+            ("\\q", 0),
+            ("a{3,2}", 1),
+            ("(?x)", 2),
+            ("(?i", 3),
+            ("(?)a", 2),
+            ("a(?-)", 4),
+            ("(?i-:a)", 4),
+            ("\\x{+41}", 0),
+            ("a\\x+4", 1),
+            ("a^b", 1),
+            ("a$b", 1),
+            ("(^a)", 1),
+            ("\\bfoo", 0),
+        ];
+        for (pattern, position) in cases {
+            let error = compile(pattern, &mut table).unwrap_err();
+            assert_eq!(error.position, position, "{}: {}", pattern, error);
+        }
+        for (pattern, position) in [("a~", 2), ("(~)", 2)] {
+            let error = compile_with_set_operations(pattern, &mut table).unwrap_err();
+            assert_eq!(error.position, position, "{}: {}", pattern, error);
+        }
+        let mut message = |pattern: &str| compile(pattern, &mut table).unwrap_err().message;
+        assert_eq!(message("(?)a"), "empty flag group");
+        assert_eq!(message("(?-)"), "missing flag after '-'");
+        assert_eq!(message("\\x{+41}"), "invalid hex escape '+41'");
+        assert!(compile("(?:a)(?i-i)", &mut table).is_ok());
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+
+use super::ast::{AssertionKind, Ast};
+use super::char_set::{partition_ranges, CharSetTable};
+use super::graph::{Graph, LexemeCategory, State, StateType};
+use super::pattern::PatternError;
+
+/// 语法树的结点,叶子结点是一个位置
+enum Node {
+    Empty,
+    /// 位置序号
+    Leaf(usize),
+    Concat(Vec<Node>),
+    Alternation(Vec<Node>),
+    Star(Box<Node>),
+}
+
+/// 结点的nullable、firstpos与lastpos
+struct Positions {
+    nullable: bool,
+    first: Vec<usize>,
+    last: Vec<usize>,
+}
+
+/// 由正则表达式直接构造DFA的中间结果:位置上的字符范围与followpos
+struct PositionTable {
+    /// 位置 -> 该位置上的字符范围;增广的结束标记#不在其中
+    ranges: Vec<Vec<(char, char)>>,
+    /// 位置 -> followpos(位置)
+    follow: Vec<Vec<usize>>,
+}
+
+impl PositionTable {
+    /// 把语法树转换为结点树,为每个字符或字符类分配一个位置;
+    /// 计数重复展开为连接,展开后的每一份有各自的位置。
+    /// 单词边界断言、交与补无法构造,返回position为0的错误
+    fn build(&mut self, ast: &Ast) -> Result<Node, PatternError> {
+        Ok(match ast {
+            Ast::Empty | Ast::Assertion(AssertionKind::StartText | AssertionKind::EndText) => {
+                Node::Empty
+            }
+            Ast::Literal(c) => self.leaf(vec![(*c, *c)]),
+            Ast::Class(ranges) => self.leaf(ranges.clone()),
+            Ast::Concat(items) => Node::Concat(
+                items
+                    .iter()
+                    .map(|item| self.build(item))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Ast::Alternation(items) => Node::Alternation(
+                items
+                    .iter()
+                    .map(|item| self.build(item))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Ast::Group(ast) => self.build(ast)?,
+            Ast::Repeat { ast, min, max } => {
+                let mut items: Vec<Node> = (0..*min)
+                    .map(|_| self.build(ast))
+                    .collect::<Result<_, _>>()?;
+                match max {
+                    None => items.push(Node::Star(Box::new(self.build(ast)?))),
+                    Some(max) => {
+                        for _ in *min..*max {
+                            let item = self.build(ast)?;
+                            items.push(Node::Alternation(vec![item, Node::Empty]));
+                        }
+                    }
+                }
+                Node::Concat(items)
+            }
+            Ast::Assertion(_) => {
+                return Err(PatternError {
+                    position: 0,
+                    message: format!("assertion {} cannot be compiled into a DFA", ast),
+                })
+            }
+            Ast::Intersection(_) | Ast::Complement(_) => {
+                return Err(PatternError {
+                    position: 0,
+                    message:
+                        "intersection and complement are not supported by the position automaton"
+                            .to_string(),
+                })
+            }
+        })
+    }
+    fn leaf(&mut self, ranges: Vec<(char, char)>) -> Node {
+        self.ranges.push(ranges);
+        self.follow.push(Vec::new());
+        Node::Leaf(self.ranges.len() - 1)
+    }
+    /// 自底向上求nullable、firstpos、lastpos,同时计算followpos:
+    /// 连接c1c2中lastpos(c1)的每个位置之后可以是firstpos(c2)中的位置,
+    /// 闭包c*中lastpos(c)的每个位置之后可以是firstpos(c)中的位置
+    fn positions(&mut self, node: &Node) -> Positions {
+        match node {
+            Node::Empty => Positions {
+                nullable: true,
+                first: Vec::new(),
+                last: Vec::new(),
+            },
+            Node::Leaf(pos) => Positions {
+                nullable: false,
+                first: vec![*pos],
+                last: vec![*pos],
+            },
+            Node::Concat(items) => {
+                let mut result = Positions {
+                    nullable: true,
+                    first: Vec::new(),
+                    last: Vec::new(),
+                };
+                for item in items.iter() {
+                    let item = self.positions(item);
+                    for &pos in result.last.iter() {
+                        union_into(&mut self.follow[pos], &item.first);
+                    }
+                    if result.nullable {
+                        union_into(&mut result.first, &item.first);
+                    }
+                    if item.nullable {
+                        union_into(&mut result.last, &item.last);
+                    } else {
+                        result.last = item.last;
+                    }
+                    result.nullable &= item.nullable;
+                }
+                result
+            }
+            Node::Alternation(items) => {
+                let mut result = Positions {
+                    nullable: false,
+                    first: Vec::new(),
+                    last: Vec::new(),
+                };
+                for item in items.iter() {
+                    let item = self.positions(item);
+                    result.nullable |= item.nullable;
+                    union_into(&mut result.first, &item.first);
+                    union_into(&mut result.last, &item.last);
+                }
+                result
+            }
+            Node::Star(item) => {
+                let item = self.positions(item);
+                for &pos in item.last.iter() {
+                    union_into(&mut self.follow[pos], &item.first);
+                }
+                Positions {
+                    nullable: true,
+                    first: item.first,
+                    last: item.last,
+                }
+            }
+        }
+    }
+}
+
+/// 有序集合的并,结果仍然有序
+fn union_into(set: &mut Vec<usize>, other: &[usize]) {
+    set.extend_from_slice(other);
+    set.sort_unstable();
+    set.dedup();
+}
+
+impl Ast {
+    /// 用位置自动机(Glushkov,即龙书中的followpos算法)由正则表达式直接构造DFA:
+    /// 对增广的正则表达式(r)#求nullable、firstpos、lastpos与followpos,
+    /// DFA的状态是位置的集合,开始状态为firstpos,含有#的位置的状态为结束状态。
+    /// 输入符号为各位置字符范围加细得到的等价类,到达同一状态的等价类合并为一条边,
+    /// CHARSET边的字符集加入到table中。得到的DFA没有ε边;
+    /// 含有交、补或单词边界断言时返回错误
+    pub fn to_position_dfa(&self, table: &mut CharSetTable) -> Result<Graph, PatternError> {
+        let mut positions = PositionTable {
+            ranges: Vec::new(),
+            follow: Vec::new(),
+        };
+        let root = positions.build(self)?;
+        let end = positions.ranges.len();
+        let root = positions.positions(&root);
+        // 增广:lastpos中的位置之后可以是#
+        let mut d_states: Vec<Vec<usize>> = vec![root.first.clone()];
+        if root.nullable {
+            d_states[0].push(end);
+        }
+        for &pos in root.last.iter() {
+            positions.follow[pos].push(end);
+        }
+        let classes = partition_ranges(&positions.ranges);
+        let mut state_map: HashMap<Vec<usize>, i32> = HashMap::new();
+        state_map.insert(d_states[0].clone(), 0);
+        let mut dfa = Graph {
+            graph_id: 0,
+            num_of_states: 0,
+            p_edge_table: Vec::new(),
+            p_state_table: Vec::new(),
+        };
+        let mut from_state = 0;
+        while (from_state as usize) < d_states.len() {
+            let state_vec = d_states[from_state as usize].clone();
+            let mut targets: Vec<(i32, Vec<(char, char)>)> = Vec::new();
+            for class in classes.iter() {
+                // U为S中对应于该等价类的所有位置p的followpos(p)的并集
+                let c = class[0].0;
+                let mut next_vec: Vec<usize> = Vec::new();
+                for &pos in state_vec.iter().filter(|&&pos| pos != end) {
+                    if positions.ranges[pos]
+                        .iter()
+                        .any(|&(from, to)| from <= c && c <= to)
+                    {
+                        union_into(&mut next_vec, &positions.follow[pos]);
+                    }
+                }
+                if next_vec.is_empty() {
+                    continue;
+                }
+                let next_state = *state_map.entry(next_vec.clone()).or_insert_with(|| {
+                    d_states.push(next_vec);
+                    d_states.len() as i32 - 1
+                });
+                match targets.iter_mut().find(|(state, _)| *state == next_state) {
+                    Some((_, ranges)) => ranges.extend_from_slice(class),
+                    None => targets.push((next_state, class.clone())),
+                }
+            }
+            for (next_state, ranges) in targets {
+                dfa.add_ranges_edge(from_state, next_state, &ranges, table);
+            }
+            dfa.p_state_table.push(State {
+                state_id: from_state,
+                state_type: if state_vec.contains(&end) {
+                    StateType::MATCH
+                } else {
+                    StateType::UNMATCH
+                },
+                category: LexemeCategory::EMPTY,
+            });
+            from_state += 1;
+        }
+        dfa.num_of_states = dfa.p_state_table.len() as i32;
+        Ok(dfa)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::DriverType;
+    use crate::pattern::{parse, PatternOptions};
+    use crate::test_util::all_strings;
+
+    fn parse_ast(pattern: &str) -> Ast {
+        parse(pattern, &PatternOptions::default()).unwrap()
+    }
+
+    /// 龙书例3.56:(a|b)*abb直接构造的DFA有4个状态
+    #[test]
+    fn test_abb() {
+        let mut table = CharSetTable::new();
+        let dfa = parse_ast("(a|b)*abb").to_position_dfa(&mut table).unwrap();
+        assert_eq!(dfa.num_of_states, 4);
+        assert!(dfa
+            .p_edge_table
+            .iter()
+            .all(|edge| edge.driver_type != DriverType::NULL));
+        assert!(dfa.simulate("babb", &table));
+        assert!(!dfa.simulate("abab", &table));
+    }
+
+    /// 与最简NFA构造法加子集构造法得到的DFA接受相同的串,且状态数不多于后者
+    #[test]
+    fn test_equivalent_to_subset_construction() {
+        let patterns = [
+            "(a|b)*abb",
+            "a+b?c*",
+            "(ab|a)(bc|c)",
+            "[a-c]*c[^b]",
+            "a{2,3}|b{2,}",
+            "(a|ab)*b",
+            "(a*b*)*",
+            "",
+            "a[^\\s\\S]*|b[^\\s\\S]",
+        ];
+        let inputs = all_strings(&['a', 'b', 'c', 'd'], 5);
+        for pattern in patterns {
+            let mut table = CharSetTable::new();
+            let ast = parse_ast(pattern);
+            let dfa = ast.to_position_dfa(&mut table).unwrap();
+            let subset = ast.to_graph(&mut table).unwrap().nfa_to_dfa(&mut table);
+            assert!(dfa.num_of_states <= subset.num_of_states, "{}", pattern);
+            for input in inputs.iter() {
+                assert_eq!(
+                    dfa.simulate(input, &table),
+                    subset.simulate(input, &table),
+                    "{} on {:?}",
+                    pattern,
+                    input
+                );
+            }
+        }
+    }
+
+    /// 无法构造的结点返回错误而不是panic
+    #[test]
+    fn test_unsupported() {
+        let mut table = CharSetTable::new();
+        let error = parse_ast(r"a\b").to_position_dfa(&mut table).unwrap_err();
+        assert_eq!(error.message, r"assertion \b cannot be compiled into a DFA");
+        let complement = Ast::Complement(Box::new(parse_ast("a")));
+        assert!(complement.to_position_dfa(&mut table).is_err());
+    }
+}
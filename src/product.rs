@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+
+use super::char_set::{complement_ranges, partition_ranges, subtract_ranges, CharSetTable};
+use super::graph::{Category, DriverType, Edge, Graph, State, StateType};
+
+impl<C: Category> Graph<C> {
+    /// DFA在状态state上读入字符c到达的状态,没有对应的边时返回None
+    pub(crate) fn dfa_next(&self, state: i32, c: char, table: &CharSetTable) -> Option<i32> {
+        self.move_by_char(&[state], c, table).first().copied()
+    }
+
+    /// 转换为**完全DFA**:任何状态在任何字符上都有转换,原来没有转换的字符
+    /// 都到达一个显式的死状态,死状态在所有字符上都回到自身。
+    /// self可以是NFA,先用子集构造法确定化;字符集加入到table中
+    pub fn to_total_dfa(&self, table: &mut CharSetTable) -> Graph<C> {
+        let dfa = self.nfa_to_dfa(table);
+        // 字母表的等价类,再加上不出现在任何边上的字符
+        let mut classes = dfa.alphabet_classes(table);
+        let covered: Vec<(char, char)> = classes.concat();
+        let rest = subtract_ranges(&complement_ranges(&[]), &covered);
+        if !rest.is_empty() {
+            classes.push(rest);
+        }
+        let dead = dfa.p_state_table.len() as i32;
+        let mut total = Graph {
+            graph_id: dfa.graph_id,
+            num_of_states: 0,
+            p_edge_table: Vec::new(),
+            p_state_table: dfa.p_state_table.clone(),
+        };
+        let mut has_dead = false;
+        let mut from_state = 0;
+        while from_state < dead || (has_dead && from_state == dead) {
+            let mut targets: Vec<(i32, Vec<(char, char)>)> = Vec::new();
+            for class in classes.iter() {
+                let next_state = match from_state {
+                    state if state == dead => dead,
+                    state => dfa.dfa_next(state, class[0].0, table).unwrap_or(dead),
+                };
+                has_dead |= next_state == dead;
+                match targets.iter_mut().find(|(state, _)| *state == next_state) {
+                    Some((_, ranges)) => ranges.extend_from_slice(class),
+                    None => targets.push((next_state, class.clone())),
+                }
+            }
+            for (next_state, ranges) in targets {
+                total.add_ranges_edge(from_state, next_state, &ranges, table);
+            }
+            from_state += 1;
+        }
+        if has_dead {
+            total.p_state_table.push(State {
+                state_id: dead,
+                state_type: StateType::UNMATCH,
+                category: C::empty(),
+            });
+        }
+        total.num_of_states = total.p_state_table.len() as i32;
+        total
+    }
+    /// **补**:接受self不接受的所有串。在完全DFA上交换结束状态与非结束状态,
+    /// 得到的DFA是完全DFA,原来的死状态成为接受一切后缀的结束状态
+    pub fn complement(&self, table: &mut CharSetTable) -> Graph<C> {
+        let mut dfa = self.to_total_dfa(table);
+        for state in dfa.p_state_table.iter_mut() {
+            state.state_type = match state.state_type {
+                StateType::MATCH => StateType::UNMATCH,
+                StateType::UNMATCH => StateType::MATCH,
+            };
+            state.category = C::empty();
+        }
+        dfa
+    }
+    /// **交**:用乘积构造法得到同时接受self与graph的串的DFA。
+    /// 乘积状态(p, q)读入字符c到达(p经c到达的状态, q经c到达的状态),
+    /// p与q都是结束状态时为结束状态,其种别取self中p的种别。
+    /// self与graph可以是NFA,先用子集构造法确定化;字符集加入到table中
+    pub fn intersect(&self, graph: &Graph<C>, table: &mut CharSetTable) -> Graph<C> {
+        let (left, right) = (self.nfa_to_dfa(table), graph.nfa_to_dfa(table));
+        let mut sets = left.edge_ranges(table);
+        sets.extend(right.edge_ranges(table));
+        let classes = partition_ranges(&sets);
+        let mut pairs: Vec<(i32, i32)> = vec![(0, 0)];
+        let mut pair_map: HashMap<(i32, i32), i32> = HashMap::new();
+        pair_map.insert((0, 0), 0);
+        let mut product = Graph {
+            graph_id: self.graph_id,
+            num_of_states: 0,
+            p_edge_table: Vec::new(),
+            p_state_table: Vec::new(),
+        };
+        let mut from_state = 0;
+        while (from_state as usize) < pairs.len() {
+            let (p, q) = pairs[from_state as usize];
+            let mut targets: Vec<(i32, Vec<(char, char)>)> = Vec::new();
+            for class in classes.iter() {
+                let c = class[0].0;
+                let next = match (left.dfa_next(p, c, table), right.dfa_next(q, c, table)) {
+                    (Some(p), Some(q)) => (p, q),
+                    _ => continue,
+                };
+                let next_state = *pair_map.entry(next).or_insert_with(|| {
+                    pairs.push(next);
+                    pairs.len() as i32 - 1
+                });
+                match targets.iter_mut().find(|(state, _)| *state == next_state) {
+                    Some((_, ranges)) => ranges.extend_from_slice(class),
+                    None => targets.push((next_state, class.clone())),
+                }
+            }
+            for (next_state, ranges) in targets {
+                product.add_ranges_edge(from_state, next_state, &ranges, table);
+            }
+            let (p_state, q_state) = (
+                &left.p_state_table[p as usize],
+                &right.p_state_table[q as usize],
+            );
+            let is_match =
+                p_state.state_type == StateType::MATCH && q_state.state_type == StateType::MATCH;
+            product.p_state_table.push(State {
+                state_id: from_state,
+                state_type: if is_match {
+                    StateType::MATCH
+                } else {
+                    StateType::UNMATCH
+                },
+                category: if is_match {
+                    p_state.category
+                } else {
+                    C::empty()
+                },
+            });
+            from_state += 1;
+        }
+        product.num_of_states = product.p_state_table.len() as i32;
+        product
+    }
+    /// 把DFA改写为最简NFA构造法要求的形式:加入一个新的结束状态作为状态表的最后一项,
+    /// 原来的结束状态改为非结束状态并经ε边到达新的结束状态,
+    /// 使结果可以继续参与并、连接与闭包运算
+    pub(crate) fn dfa_to_nfa(&self) -> Graph<C> {
+        let mut nfa = self.clone();
+        let end_state = nfa.p_state_table.len() as i32;
+        for state in nfa.p_state_table.iter_mut() {
+            if state.state_type == StateType::MATCH {
+                state.state_type = StateType::UNMATCH;
+                nfa.p_edge_table.push(Edge {
+                    from_state: state.state_id,
+                    next_state: end_state,
+                    driver_id: -1,
+                    driver_type: DriverType::NULL,
+                });
+            }
+            state.category = C::empty();
+        }
+        nfa.p_state_table.push(State {
+            state_id: end_state,
+            state_type: StateType::MATCH,
+            category: C::empty(),
+        });
+        nfa.num_of_states = nfa.p_state_table.len() as i32;
+        nfa
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::compile;
+
+    fn dfa(pattern: &str, table: &mut CharSetTable) -> Graph {
+        compile(pattern, table).unwrap().nfa_to_dfa(table)
+    }
+
+    /// 完全DFA:每个状态在每个等价类上都有转换
+    #[test]
+    fn test_total_dfa() {
+        let mut table = CharSetTable::new();
+        let total = dfa("ab", &mut table).to_total_dfa(&mut table);
+        // 0 -a-> 1 -b-> 2,加上死状态3
+        assert_eq!(total.num_of_states, 4);
+        for state in 0..total.num_of_states {
+            for c in ['a', 'b', 'z', '中'] {
+                assert!(
+                    total.dfa_next(state, c, &table).is_some(),
+                    "{} {}",
+                    state,
+                    c
+                );
+            }
+        }
+        assert!(total.simulate("ab", &table));
+        assert!(!total.simulate("abz", &table));
+    }
+
+    /// 补
+    #[test]
+    fn test_complement() {
+        let mut table = CharSetTable::new();
+        let not_ab = dfa("ab", &mut table).complement(&mut table);
+        for (input, expected) in [
+            ("ab", false),
+            ("", true),
+            ("a", true),
+            ("abc", true),
+            ("x", true),
+        ] {
+            assert_eq!(not_ab.simulate(input, &table), expected, "{}", input);
+        }
+        let twice = not_ab.complement(&mut table);
+        assert!(twice.simulate("ab", &table));
+        assert!(!twice.simulate("a", &table));
+    }
+
+    /// 交:不是关键字的标识符
+    #[test]
+    fn test_intersect() {
+        let mut table = CharSetTable::new();
+        let id = dfa("[a-z][a-z0-9]*", &mut table);
+        let keyword = dfa("if|int|return", &mut table);
+        let not_keyword = id.intersect(&keyword.complement(&mut table), &mut table);
+        for (input, expected) in [
+            ("if", false),
+            ("int", false),
+            ("return", false),
+            ("i", true),
+            ("in", true),
+            ("ints", true),
+            ("x1", true),
+            ("1x", false),
+        ] {
+            assert_eq!(not_keyword.simulate(input, &table), expected, "{}", input);
+        }
+        // 交为空
+        let empty = dfa("a+", &mut table).intersect(&dfa("b+", &mut table), &mut table);
+        assert!(!empty.simulate("a", &table) && !empty.simulate("b", &table));
+    }
+}
@@ -1,7 +1,7 @@
 #![allow(unused, non_camel_case_types)]
-use super::graph::{LexemeCategory,Graph};
-/// **正则运算**的数据结构定义
-pub struct regularExpression {
+use super::graph::{Graph, LexemeCategory};
+/// **正则运算**的数据结构定义,词的类别为C
+pub struct regularExpression<C = LexemeCategory> {
     pub regular_id: i32,
     pub name: String,
     /// 正则运算符
@@ -17,9 +17,9 @@ pub struct regularExpression {
     /// 运算结果的类型
     pub result_type: OperandType,
     /// 词的category属性值
-    pub category: LexemeCategory,
+    pub category: C,
     /// 对应的NFA
-    pub p_nfa: Graph,
+    pub p_nfa: Graph<C>,
 }
 /// 操作数的类型
 pub enum OperandType {
@@ -32,4 +32,3 @@ pub enum OperandType {
     /// 无
     NONE,
 }
-
@@ -0,0 +1,260 @@
+use super::char_set::CharSetTable;
+use super::graph::LexemeCategory;
+use super::lexer::RuleSet;
+
+/// 整数后缀:u与l、ll的组合,大小写均可,ll不能写成lL
+macro_rules! integer_suffix {
+    () => {
+        "(?:[uU](?:ll|LL|[lL])?|(?:ll|LL|[lL])[uU]?)?"
+    };
+}
+
+/// 十进制整数,如`42`、`42u`、`42ULL`
+pub const DECIMAL_INTEGER: &str = concat!("[1-9][0-9]*", integer_suffix!());
+/// 八进制整数,以0开头,单独的`0`也是八进制整数
+pub const OCTAL_INTEGER: &str = concat!("0[0-7]*", integer_suffix!());
+/// 十六进制整数,如`0x1F`、`0XffL`
+pub const HEX_INTEGER: &str = concat!("0[xX][0-9a-fA-F]+", integer_suffix!());
+/// 二进制整数,如`0b1010`
+pub const BINARY_INTEGER: &str = concat!("0[bB][01]+", integer_suffix!());
+/// 以上四种整数
+pub const INTEGER: &str = concat!(
+    "(?:[1-9][0-9]*|0[0-7]*|0[xX][0-9a-fA-F]+|0[bB][01]+)",
+    integer_suffix!()
+);
+/// 不带指数的实数,如`3.14`、`1.`、`.5f`
+pub const FLOAT: &str = r"(?:[0-9]+\.[0-9]*|\.[0-9]+)[fFlL]?";
+/// 科学计数法,如`1e10`、`6.02E+23`、`.5e-3f`
+pub const SCIENTIFIC: &str = r"(?:[0-9]+\.?[0-9]*|\.[0-9]+)[eE][+-]?[0-9]+[fFlL]?";
+/// 带转义的字符串常量:简单转义、八进制转义`\0`~`\377`、`\x..`、`\u....`、`\U........`,
+/// 不能跨行。`"\777"`会被读作`\77`与`7`,超出范围的八进制转义由`decode_string`报错
+pub const STRING: &str = concat!(
+    r#""(?:[^"\\\n]|\\(?:['"?\\abfnrtv]|[0-3]?[0-7]{1,2}|x[0-9a-fA-F]+"#,
+    r#"|u[0-9a-fA-F]{4}|U[0-9a-fA-F]{8}))*""#
+);
+/// 行注释,不含行尾的换行
+pub const LINE_COMMENT: &str = r"//[^\n]*";
+/// 块注释,不能嵌套;可嵌套的块注释用匹配`/\*`的规则加上SubScanner::Nested
+pub const BLOCK_COMMENT: &str = r"/\*(?:[^*]|\*+[^*/])*\*+/";
+/// 标识符
+pub const IDENTIFIER: &str = "[A-Za-z_][A-Za-z0-9_]*";
+/// 比较运算符
+pub const COMPARE_OPERATOR: &str = "==|!=|<=|>=|<|>";
+/// 逻辑运算符
+pub const LOGIC_OPERATOR: &str = r"\&\&|\|\||!";
+/// 其他运算符与分隔符,含复合赋值
+pub const OPERATOR: &str = r"\+\+|--|->|<<=?|>>=?|[-+*/%^|]=?|\&=?|[=\~?:.,;()\[\]{}]";
+/// 空白
+pub const WHITESPACE: &str = r"[ \t\r\n\f\v]+";
+
+/// C风格语言的一组规则,每条规则的种别为对应的LexemeCategory,字符集加入到table中
+pub fn c_like_rules(table: &mut CharSetTable) -> RuleSet {
+    let mut rules = RuleSet::new();
+    for (category, pattern) in [
+        (LexemeCategory::NOTE, BLOCK_COMMENT),
+        (LexemeCategory::NOTE, LINE_COMMENT),
+        (LexemeCategory::STRING_CONST, STRING),
+        (LexemeCategory::SCIENTIFIC_CONST, SCIENTIFIC),
+        (LexemeCategory::FLOAT_CONST, FLOAT),
+        (LexemeCategory::INTEGER_CONST, INTEGER),
+        (LexemeCategory::ID, IDENTIFIER),
+        (LexemeCategory::COMPARE_OPERATOR, COMPARE_OPERATOR),
+        (LexemeCategory::LOGIC_OPERATOR, LOGIC_OPERATOR),
+        (LexemeCategory::OPERATOR, OPERATOR),
+        (LexemeCategory::SPACE_CONST, WHITESPACE),
+    ] {
+        rules.add(category, pattern, table).unwrap();
+    }
+    rules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::{Decoders, TokenValue};
+    use crate::lexer::RuleStatus;
+    use crate::pattern::compile;
+    use crate::tokenizer::LexErrorKind;
+
+    /// 用接受与拒绝的语料检查一条规则
+    fn check(pattern: &str, accept: &[&str], reject: &[&str]) {
+        let mut table = CharSetTable::new();
+        let nfa = compile(pattern, &mut table).unwrap();
+        for input in accept {
+            assert!(
+                nfa.simulate(input, &table),
+                "{} should accept {:?}",
+                pattern,
+                input
+            );
+        }
+        for input in reject {
+            assert!(
+                !nfa.simulate(input, &table),
+                "{} should reject {:?}",
+                pattern,
+                input
+            );
+        }
+    }
+
+    /// 整数
+    #[test]
+    fn test_integers() {
+        check(
+            DECIMAL_INTEGER,
+            &["1", "42", "42u", "42UL", "42ull", "42LLU", "9lu"],
+            &["0", "012", "42lL", "42uu", "4 2", "42a"],
+        );
+        check(OCTAL_INTEGER, &["0", "017", "00u"], &["08", "1", "0x1"]);
+        check(
+            HEX_INTEGER,
+            &["0x0", "0X1f", "0xDEADbeefULL"],
+            &["0x", "0xg", "x1"],
+        );
+        check(BINARY_INTEGER, &["0b0", "0B1011l"], &["0b", "0b2"]);
+        check(
+            INTEGER,
+            &["0", "7", "0755", "0xff", "0b11", "10u"],
+            &["", "09", "1.0", "-1"],
+        );
+    }
+
+    /// 实数与科学计数法
+    #[test]
+    fn test_floats() {
+        check(
+            FLOAT,
+            &["3.14", "1.", ".5", "0.0f", "2.5L"],
+            &["1", ".", "1e5", "1.5.2", "f"],
+        );
+        check(
+            SCIENTIFIC,
+            &["1e10", "6.02E+23", ".5e-3f", "1.e5", "0E0L"],
+            &["1e", "e5", "1.5", "1e+", "1e5.0"],
+        );
+    }
+
+    /// 字符串常量
+    #[test]
+    fn test_strings() {
+        check(
+            STRING,
+            &[
+                r#""""#,
+                r#""hello""#,
+                r#""a\"b""#,
+                r#""\n\t\\\0""#,
+                r#""\x41\101é\U0001F600""#,
+                "\"中文\"",
+            ],
+            &[
+                r#"""#,
+                r#""abc"#,
+                r#""\q""#,
+                r#""\u12""#,
+                "\"a\nb\"",
+                r#""a"b""#,
+            ],
+        );
+        // 规则接受`"\777"`,解码时按三位八进制数字读取并报告超出范围
+        let mut table = CharSetTable::new();
+        let lexer = c_like_rules(&mut table).to_dfa(&mut table);
+        let decoders = Decoders::standard();
+        let (tokens, errors) = lexer
+            .tokenize(r#""\377" "\777""#.as_bytes(), &table)
+            .with_decoders(&decoders)
+            .collect_all();
+        assert_eq!(tokens[0].value, Some(TokenValue::Str("\u{FF}".to_string())));
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0].kind,
+            LexErrorKind::InvalidLiteral(message) if message == "octal escape sequence out of range"
+        ));
+    }
+
+    /// 注释
+    #[test]
+    fn test_comments() {
+        check(
+            LINE_COMMENT,
+            &["//", "// note", "//* x */"],
+            &["/", "// a\n", "/ /"],
+        );
+        check(
+            BLOCK_COMMENT,
+            &[
+                "/**/",
+                "/* a */",
+                "/* a\n b */",
+                "/***/",
+                "/* ** / */",
+                "/*/ */",
+            ],
+            &["/*/", "/* */ */", "/* a", "/ * */"],
+        );
+    }
+
+    /// 运算符、标识符与空白
+    #[test]
+    fn test_operators() {
+        check(
+            COMPARE_OPERATOR,
+            &["==", "!=", "<", ">="],
+            &["=", "=<", "<>"],
+        );
+        check(LOGIC_OPERATOR, &["&&", "||", "!"], &["&", "|", "!!"]);
+        check(
+            OPERATOR,
+            &[
+                "+", "++", "+=", "->", "<<=", "&", "&=", "~", "|", "^=", "(", "]", ";",
+            ],
+            &["+++", "&&", "<", "=>", "**"],
+        );
+        check(IDENTIFIER, &["x", "_tmp1", "Abc_9"], &["1x", "a-b", ""]);
+        check(WHITESPACE, &[" ", "\t\r\n", "\u{B}\u{C}"], &["", " a"]);
+    }
+
+    /// 规则集合中没有被遮蔽的规则,能正确切分一段代码
+    #[test]
+    fn test_c_like_rules() {
+        let mut table = CharSetTable::new();
+        let rules = c_like_rules(&mut table);
+        for report in rules.analyze(&mut table) {
+            assert_eq!(report.status, RuleStatus::Reachable, "{}", report);
+        }
+        let lexer = rules.to_dfa(&mut table);
+        let input = "x+=0x1Fu; /* c */ if (a<=1.5e3&&!b) s=\"\\\"\";// end";
+        let tokens: Vec<(LexemeCategory, String)> = lexer
+            .tokenize(input.as_bytes(), &table)
+            .map(|token| token.unwrap())
+            .filter(|token| token.category != LexemeCategory::SPACE_CONST)
+            .map(|token| (token.category, token.text))
+            .collect();
+        let expected = [
+            (LexemeCategory::ID, "x"),
+            (LexemeCategory::OPERATOR, "+="),
+            (LexemeCategory::INTEGER_CONST, "0x1Fu"),
+            (LexemeCategory::OPERATOR, ";"),
+            (LexemeCategory::NOTE, "/* c */"),
+            (LexemeCategory::ID, "if"),
+            (LexemeCategory::OPERATOR, "("),
+            (LexemeCategory::ID, "a"),
+            (LexemeCategory::COMPARE_OPERATOR, "<="),
+            (LexemeCategory::SCIENTIFIC_CONST, "1.5e3"),
+            (LexemeCategory::LOGIC_OPERATOR, "&&"),
+            (LexemeCategory::LOGIC_OPERATOR, "!"),
+            (LexemeCategory::ID, "b"),
+            (LexemeCategory::OPERATOR, ")"),
+            (LexemeCategory::ID, "s"),
+            (LexemeCategory::OPERATOR, "="),
+            (LexemeCategory::STRING_CONST, "\"\\\"\""),
+            (LexemeCategory::OPERATOR, ";"),
+            (LexemeCategory::NOTE, "// end"),
+        ];
+        assert_eq!(tokens.len(), expected.len());
+        for (token, (category, text)) in tokens.iter().zip(expected) {
+            assert_eq!((token.0, token.1.as_str()), (category, text));
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use std::fmt::Display;
+
+/// 输入中的一个位置,行号与列号从1开始
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    /// 在整个输入中的字节偏移
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    /// 输入的开头
+    pub fn start() -> Position {
+        Position {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position::start()
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// 输入中的一段,[start, end)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    /// 长度为0的一段
+    pub fn empty(position: Position) -> Span {
+        Span {
+            start: position,
+            end: position,
+        }
+    }
+    /// 字节长度
+    pub fn len(&self) -> usize {
+        self.end.offset - self.start.offset
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// 列号的计数单位
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColumnUnit {
+    /// 每个字符算一列
+    #[default]
+    Char,
+    /// 按UTF-16编码单元计数,基本平面以外的字符算两列(LSP使用的列号)
+    Utf16,
+}
+
+/// 行号与列号的计算方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpanOptions {
+    /// 制表符跳到下一个制表位,制表位的间隔为tab_width列;为1时制表符算一列
+    pub tab_width: usize,
+    pub column_unit: ColumnUnit,
+}
+
+impl Default for SpanOptions {
+    fn default() -> Self {
+        SpanOptions {
+            tab_width: 1,
+            column_unit: ColumnUnit::Char,
+        }
+    }
+}
+
+impl SpanOptions {
+    /// 从position开始读过text后到达的位置,'\n'开始新的一行
+    pub fn advance(&self, position: Position, text: &str) -> Position {
+        let mut position = position;
+        for c in text.chars() {
+            position.offset += c.len_utf8();
+            match c {
+                '\n' => {
+                    position.line += 1;
+                    position.column = 1;
+                }
+                '\t' => {
+                    let tab_width = self.tab_width.max(1);
+                    position.column = (position.column - 1) / tab_width * tab_width + tab_width + 1;
+                }
+                _ => {
+                    position.column += match self.column_unit {
+                        ColumnUnit::Char => 1,
+                        ColumnUnit::Utf16 => c.len_utf16(),
+                    }
+                }
+            }
+        }
+        position
+    }
+    /// 从position开始的text所占的一段
+    pub fn span(&self, position: Position, text: &str) -> Span {
+        Span {
+            start: position,
+            end: self.advance(position, text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 行号、列号与字节偏移
+    #[test]
+    fn test_advance() {
+        let options = SpanOptions::default();
+        let end = options.advance(Position::start(), "ab\ncd");
+        assert_eq!(
+            end,
+            Position {
+                offset: 5,
+                line: 2,
+                column: 3
+            }
+        );
+        let span = options.span(end, "变量");
+        assert_eq!(span.len(), 6);
+        assert_eq!(span.to_string(), "2:3-2:5");
+    }
+
+    /// 制表符与UTF-16列号
+    #[test]
+    fn test_options() {
+        let tab = SpanOptions {
+            tab_width: 4,
+            ..SpanOptions::default()
+        };
+        assert_eq!(tab.advance(Position::start(), "\t").column, 5);
+        assert_eq!(tab.advance(Position::start(), "ab\t").column, 5);
+        assert_eq!(tab.advance(Position::start(), "abcd\tx").column, 10);
+        assert_eq!(
+            SpanOptions::default()
+                .advance(Position::start(), "\t\t")
+                .column,
+            3
+        );
+        let utf16 = SpanOptions {
+            column_unit: ColumnUnit::Utf16,
+            ..SpanOptions::default()
+        };
+        assert_eq!(utf16.advance(Position::start(), "a😀b").column, 5);
+        assert_eq!(
+            SpanOptions::default()
+                .advance(Position::start(), "a😀b")
+                .column,
+            4
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+
+use super::ast::Ast;
+use super::char_set::CharSetTable;
+use super::graph::{Category, DriverType, Graph, StateType};
+
+/// 广义NFA:边上是正则表达式,(起点, 终点) -> 边上的正则表达式,平行边合并为选择
+struct Gnfa {
+    edges: BTreeMap<(usize, usize), Ast>,
+}
+
+impl Gnfa {
+    /// 加入一条边,已有平行边时与其合并为选择
+    fn add_edge(&mut self, from: usize, to: usize, ast: Ast) {
+        let ast = match self.edges.remove(&(from, to)) {
+            Some(old) => Ast::Alternation(vec![old, ast]).simplify(),
+            None => ast,
+        };
+        self.edges.insert((from, to), ast);
+    }
+    /// 进入与离开结点的边数(不含自环)
+    fn degree(&self, node: usize) -> (usize, usize) {
+        let mut degree = (0, 0);
+        for &(from, to) in self.edges.keys() {
+            if from == to {
+                continue;
+            }
+            if to == node {
+                degree.0 += 1;
+            }
+            if from == node {
+                degree.1 += 1;
+            }
+        }
+        degree
+    }
+    /// 消去结点q:对每对边p->q、q->r,加入边p->r,其上为R(p,q)R(q,q)*R(q,r)
+    fn eliminate(&mut self, q: usize) {
+        let self_loop = self
+            .edges
+            .remove(&(q, q))
+            .map(|ast| Ast::repeat(ast, 0, None));
+        let ins: Vec<(usize, Ast)> = self
+            .edges
+            .iter()
+            .filter(|((_, to), _)| *to == q)
+            .map(|(&(from, _), ast)| (from, ast.clone()))
+            .collect();
+        let outs: Vec<(usize, Ast)> = self
+            .edges
+            .iter()
+            .filter(|((from, _), _)| *from == q)
+            .map(|(&(_, to), ast)| (to, ast.clone()))
+            .collect();
+        self.edges.retain(|&(from, to), _| from != q && to != q);
+        for (p, in_ast) in ins.iter() {
+            for (r, out_ast) in outs.iter() {
+                let mut items = vec![in_ast.clone()];
+                items.extend(self_loop.clone());
+                items.push(out_ast.clone());
+                self.add_edge(*p, *r, Ast::Concat(items).simplify());
+            }
+        }
+    }
+}
+
+impl<C: Category> Graph<C> {
+    /// 用**状态消去法**(Brzozowski–McCluskey)把自动机(NFA或DFA)转换为等价的正则表达式:
+    /// 加入新的开始结点与结束结点后,每次消去进出边数之积最小的状态,
+    /// 每一步都做代数化简以得到可读的结果。CHARSET边的字符集在table中查找。
+    /// 不接受任何串时返回空的字符类,输出为能重新分析的`[^\s\S]`
+    pub fn to_ast(&self, table: &CharSetTable) -> Ast {
+        let ids: Vec<i32> = self
+            .p_state_table
+            .iter()
+            .map(|state| state.state_id)
+            .collect();
+        let node_of = |state_id: i32| {
+            ids.iter()
+                .position(|&id| id == state_id)
+                .unwrap_or_else(|| panic!("state {} is not in the state table", state_id))
+        };
+        let (start, end) = (ids.len(), ids.len() + 1);
+        let mut gnfa = Gnfa {
+            edges: BTreeMap::new(),
+        };
+        gnfa.add_edge(start, node_of(0), Ast::Empty);
+        for state in self.p_state_table.iter() {
+            if state.state_type == StateType::MATCH {
+                gnfa.add_edge(node_of(state.state_id), end, Ast::Empty);
+            }
+        }
+        for edge in self.p_edge_table.iter() {
+            let ast = match edge.driver_type {
+                DriverType::NULL => Ast::Empty,
+                DriverType::CHAR => match char::from_u32(edge.driver_id as u32) {
+                    Some(c) => Ast::Literal(c),
+                    None => continue,
+                },
+                DriverType::CHARSET => Ast::class(&table.ranges(edge.driver_id)).simplify(),
+            };
+            gnfa.add_edge(node_of(edge.from_state), node_of(edge.next_state), ast);
+        }
+        let mut remaining: Vec<usize> = (0..ids.len()).collect();
+        while !remaining.is_empty() {
+            let (index, _) = remaining
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, &node)| {
+                    let (ins, outs) = gnfa.degree(node);
+                    ins * outs
+                })
+                .unwrap();
+            let node = remaining.remove(index);
+            gnfa.eliminate(node);
+        }
+        gnfa.edges
+            .remove(&(start, end))
+            .unwrap_or(Ast::Class(Vec::new()))
+    }
+    /// 转换为等价的正则表达式文本,见to_ast
+    pub fn to_regex(&self, table: &CharSetTable) -> String {
+        self.to_ast(table).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::equivalence::Verdict;
+    use crate::pattern::compile;
+    use crate::test_util::{all_strings, compile_with_set_operations};
+
+    /// 转换得到的正则表达式与原自动机接受相同的串
+    #[test]
+    fn test_round_trip_language() {
+        let patterns = [
+            "(a|b)*abb",
+            "a+b?",
+            "(ab|c)*",
+            "a(b|c)*d|e",
+            "[a-c]x[^a]",
+            "(a|b)(a|b)",
+            "",
+        ];
+        let inputs = all_strings(&['a', 'b', 'c', 'd', 'e', 'x'], 4);
+        for pattern in patterns {
+            let mut table = CharSetTable::new();
+            let graph = compile(pattern, &mut table).unwrap();
+            let regex = graph.to_regex(&table);
+            let converted = compile(&regex, &mut table).unwrap();
+            for input in inputs.iter() {
+                assert_eq!(
+                    converted.simulate(input, &table),
+                    graph.simulate(input, &table),
+                    "{} -> {} on {:?}",
+                    pattern,
+                    regex,
+                    input
+                );
+            }
+        }
+    }
+
+    /// 化简后的结果可读
+    #[test]
+    fn test_readable() {
+        let cases = [
+            ("a+", "a+"),
+            ("(ab|c)*", "(?:c|ab)*"),
+            ("(a|b)*abb", "[ab]*abb"),
+            ("ab|ac", "a[bc]"),
+            ("a*", "a*"),
+            ("a?b", "a?b"),
+            ("[0-9]+", "[0-9]+"),
+        ];
+        for (pattern, expected) in cases {
+            let mut table = CharSetTable::new();
+            let graph = compile(pattern, &mut table).unwrap();
+            assert_eq!(graph.to_regex(&table), expected, "{}", pattern);
+        }
+        // 去掉所有边后不接受任何串
+        let mut table = CharSetTable::new();
+        let mut graph = compile("ab", &mut table).unwrap();
+        graph.p_edge_table.clear();
+        assert_eq!(graph.to_ast(&table), Ast::Class(Vec::new()));
+        assert_eq!(graph.to_regex(&table), "[^\\s\\S]");
+    }
+
+    /// 空语言转换得到的正则表达式能重新编译,且仍不接受任何串
+    #[test]
+    fn test_empty_language() {
+        let mut table = CharSetTable::new();
+        let graph = compile_with_set_operations("a&b", &mut table).unwrap();
+        let regex = graph.to_regex(&table);
+        let converted = compile(&regex, &mut table).unwrap();
+        assert_eq!(converted.is_empty(&mut table), Verdict::Holds);
+        let converted = compile(&format!("x({})*", regex), &mut table).unwrap();
+        assert!(converted.simulate("x", &table));
+        assert!(!converted.simulate("xa", &table));
+    }
+}
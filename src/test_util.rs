@@ -0,0 +1,36 @@
+use super::char_set::CharSetTable;
+use super::graph::Graph;
+use super::pattern::{compile_with_options, PatternError, PatternOptions};
+
+/// 由字母表中的字符组成的长度不超过max_len的所有串,按长度递增排列
+pub(crate) fn all_strings(alphabet: &[char], max_len: usize) -> Vec<String> {
+    let mut result = vec![String::new()];
+    let mut last = vec![String::new()];
+    for _ in 0..max_len {
+        let mut next = Vec::new();
+        for s in last.iter() {
+            for &c in alphabet.iter() {
+                next.push(format!("{}{}", s, c));
+            }
+        }
+        result.extend(next.iter().cloned());
+        last = next;
+    }
+    result
+}
+
+/// 打开`set_operations`的语法选项,`&`与`~`是交与补
+pub(crate) fn set_operations() -> PatternOptions {
+    PatternOptions {
+        set_operations: true,
+        ..PatternOptions::default()
+    }
+}
+
+/// 打开`set_operations`把正则表达式编译为NFA
+pub(crate) fn compile_with_set_operations(
+    pattern: &str,
+    table: &mut CharSetTable,
+) -> Result<Graph, PatternError> {
+    compile_with_options(pattern, table, &set_operations())
+}
@@ -0,0 +1,1159 @@
+use std::fmt::Display;
+use std::io::{self, Read};
+
+use super::char_set::CharSetTable;
+use super::decode::{Decoders, TokenValue};
+use super::graph::{Category, DriverType, Graph, LexemeCategory, StateType};
+use super::keyword::KeywordTable;
+use super::lexer::{Lexer, LexerDfa, ModeAction, SubScanner};
+use super::span::{Position, Span, SpanOptions};
+use super::trivia::{Trivia, TriviaMode};
+
+/// 默认每次从输入中读取的字节数
+const DEFAULT_CHUNK_SIZE: usize = 4096;
+
+/// 词法分析得到的一个单词
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token<C = LexemeCategory> {
+    pub category: C,
+    /// 胜出的规则的序号
+    pub rule: usize,
+    /// 词素
+    pub text: String,
+    /// 词素在输入中的位置
+    pub span: Span,
+    /// 由解码函数得到的值,该种别没有解码函数时为None
+    pub value: Option<TokenValue>,
+    /// 挂在该单词之前的附属成分(空白、注释等)
+    pub leading: Vec<Token<C>>,
+    /// 挂在该单词之后、同一行内的附属成分
+    pub trailing: Vec<Token<C>>,
+}
+
+impl<C> Token<C> {
+    /// 连同附属成分在内的原文
+    pub fn full_text(&self) -> String {
+        let mut text = String::new();
+        for token in &self.leading {
+            text += &token.text;
+        }
+        text += &self.text;
+        for token in &self.trailing {
+            text += &token.text;
+        }
+        text
+    }
+}
+
+/// 词法错误的种类
+#[derive(Debug)]
+pub enum LexErrorKind {
+    /// 读取输入时出错
+    Io(io::Error),
+    /// 输入不是合法的UTF-8
+    InvalidUtf8,
+    /// 没有规则接受以该字符开始的任何串
+    UnexpectedChar(char),
+    /// 字面量的词素不能解码,如整数溢出或不合法的转义
+    InvalidLiteral(String),
+    /// 子扫描器读到输入末尾仍没有找到结束符号,如未闭合的块注释
+    Unterminated(String),
+}
+
+/// 词法错误
+#[derive(Debug)]
+pub struct LexError {
+    /// 出错处在输入中的位置
+    pub span: Span,
+    pub kind: LexErrorKind,
+}
+
+impl Display for LexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            LexErrorKind::Io(error) => write!(f, "{}: read error: {}", self.span.start, error),
+            LexErrorKind::InvalidUtf8 => write!(f, "{}: invalid UTF-8", self.span.start),
+            LexErrorKind::UnexpectedChar(c) => {
+                write!(f, "{}: unexpected character {:?}", self.span.start, c)
+            }
+            LexErrorKind::InvalidLiteral(message) => write!(f, "{}: {}", self.span.start, message),
+            LexErrorKind::Unterminated(close) => {
+                write!(
+                    f,
+                    "{}: unterminated token, expected {:?}",
+                    self.span.start, close
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for LexError {}
+
+/// 遇到没有规则接受的字符(或不合法的UTF-8)时的恢复方式。
+/// 除Stop外,产生的Err都相当于一个错误单词,其span覆盖被跳过的输入,之后继续切分;
+/// 读取输入出错时总是结束,字面量解码失败时总是继续
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Recovery {
+    /// 产生一个Err,之后迭代结束
+    #[default]
+    Stop,
+    /// 跳过出错的一个字符
+    SkipChar,
+    /// 跳过出错的字符,以及其后直到下一个能开始某条规则的字符之前的所有字符
+    SkipToRuleStart,
+    /// 恐慌模式:跳过出错的字符,以及其后直到下一个属于同步字符集的字符之前的所有字符,
+    /// 同步字符集由其在字符集表中的index_id给出
+    Synchronize(i32),
+}
+
+/// 从缓冲区开头解码一个字符的结果
+enum Decoded {
+    /// 字符及其UTF-8编码的字节数
+    Char(char, usize),
+    /// 缓冲区中的字节不足一个字符
+    Incomplete,
+    /// 不是合法的UTF-8
+    Invalid,
+}
+
+fn decode_char(bytes: &[u8]) -> Decoded {
+    let bytes = &bytes[..bytes.len().min(4)];
+    let valid = match std::str::from_utf8(bytes) {
+        Ok(s) => s,
+        Err(error) if error.valid_up_to() > 0 => {
+            std::str::from_utf8(&bytes[..error.valid_up_to()]).unwrap()
+        }
+        Err(error) => {
+            return match error.error_len() {
+                Some(_) => Decoded::Invalid,
+                None => Decoded::Incomplete,
+            }
+        }
+    };
+    match valid.chars().next() {
+        Some(c) => Decoded::Char(c, c.len_utf8()),
+        None => Decoded::Incomplete,
+    }
+}
+
+/// DFA的转换表:每个状态一组按字符升序排列、互不相交的(开始, 结束, 下一个状态),
+/// 用二分查找转换,每个字符的代价与边和字符范围的总数无关
+#[derive(Clone, Debug)]
+struct Transitions {
+    states: Vec<Vec<(char, char, i32)>>,
+}
+
+impl Transitions {
+    fn new<C: Category>(dfa: &Graph<C>, table: &CharSetTable) -> Transitions {
+        let mut states = vec![Vec::new(); dfa.p_state_table.len()];
+        for edge in dfa.p_edge_table.iter() {
+            let ranges = match edge.driver_type {
+                DriverType::NULL => continue,
+                DriverType::CHAR => match char::from_u32(edge.driver_id as u32) {
+                    Some(c) => vec![(c, c)],
+                    None => continue,
+                },
+                DriverType::CHARSET => table.ranges(edge.driver_id),
+            };
+            let row: &mut Vec<(char, char, i32)> = &mut states[edge.from_state as usize];
+            row.extend(
+                ranges
+                    .iter()
+                    .map(|&(start, end)| (start, end, edge.next_state)),
+            );
+        }
+        for row in states.iter_mut() {
+            row.sort();
+        }
+        Transitions { states }
+    }
+    /// 状态state在字符c上的转换
+    fn next(&self, state: i32, c: char) -> Option<i32> {
+        Transitions::lookup(&self.states[state as usize], c)
+    }
+    /// 在一个状态的转换中查找字符c
+    fn lookup(row: &[(char, char, i32)], c: char) -> Option<i32> {
+        let index = row.partition_point(|&(_, end, _)| end < c);
+        match row.get(index) {
+            Some(&(start, _, next)) if start <= c => Some(next),
+            _ => None,
+        }
+    }
+}
+
+/// **流式词法分析器**:从任意`Read`中按需读取输入,用规则合并得到的DFA按最长匹配逐个切分单词,
+/// 作为迭代器惰性地产生单词。缓冲区中只保留当前单词及最长匹配需要向前看的字节,
+/// 跨越读取边界(包括切断一个字符的UTF-8编码)的单词与一次读入时的结果相同。
+/// 遇到错误时产生一个Err,之后按恢复方式继续或结束。
+/// 由带模式的词法分析器构造时,用模式栈顶的模式的DFA切分,规则胜出后按其操作改变模式
+pub struct Tokenizer<'a, R, C = LexemeCategory> {
+    /// 每个模式的DFA
+    dfas: &'a [LexerDfa<C>],
+    /// 每个模式的DFA的转换表,构造时建立
+    transitions: Vec<Transitions>,
+    /// 带模式的词法分析器,由单个DFA构造时为None
+    lexer: Option<&'a Lexer<C>>,
+    /// 模式栈,元素为模式的序号,栈顶为当前模式
+    modes: Vec<usize>,
+    table: &'a CharSetTable,
+    reader: R,
+    /// 每次从输入中读取的字节数
+    chunk_size: usize,
+    /// 行号与列号的计算方式
+    options: SpanOptions,
+    recovery: Recovery,
+    /// 关键字表,其指定种别的单词在其中查找并重新分类
+    keywords: Option<&'a KeywordTable<C>>,
+    /// 字面量的解码函数
+    decoders: Option<&'a Decoders<C>>,
+    /// 附属成分规则
+    trivia: Option<&'a Trivia<C>>,
+    /// 还没有遇到下一个普通单词的附属成分
+    leading: Vec<Token<C>>,
+    /// 正在收集trailing的普通单词
+    held: Option<Token<C>>,
+    /// 在held之后产生的错误
+    pending: Option<LexError>,
+    buffer: Vec<u8>,
+    /// buffer[0]在输入中的位置
+    position: Position,
+    /// 输入已经读完
+    eof: bool,
+    /// 迭代已经结束
+    done: bool,
+}
+
+impl<'a, R: Read, C: Category> Tokenizer<'a, R, C> {
+    /// 构造函数,lexer中CHARSET边的driver_id在table中查找
+    pub fn new(lexer: &'a LexerDfa<C>, table: &'a CharSetTable, reader: R) -> Tokenizer<'a, R, C> {
+        Tokenizer::build(std::slice::from_ref(lexer), None, table, reader)
+    }
+    /// 由带模式的词法分析器构造,从INITIAL模式开始
+    pub fn from_lexer(
+        lexer: &'a Lexer<C>,
+        table: &'a CharSetTable,
+        reader: R,
+    ) -> Tokenizer<'a, R, C> {
+        Tokenizer::build(&lexer.dfas, Some(lexer), table, reader)
+    }
+    fn build(
+        dfas: &'a [LexerDfa<C>],
+        lexer: Option<&'a Lexer<C>>,
+        table: &'a CharSetTable,
+        reader: R,
+    ) -> Tokenizer<'a, R, C> {
+        Tokenizer {
+            dfas,
+            transitions: dfas
+                .iter()
+                .map(|lexer_dfa| Transitions::new(&lexer_dfa.dfa, table))
+                .collect(),
+            lexer,
+            modes: vec![0],
+            table,
+            reader,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            options: SpanOptions::default(),
+            recovery: Recovery::Stop,
+            keywords: None,
+            decoders: None,
+            trivia: None,
+            leading: Vec::new(),
+            held: None,
+            pending: None,
+            buffer: Vec::new(),
+            position: Position::start(),
+            eof: false,
+            done: false,
+        }
+    }
+    /// 设置每次从输入中读取的字节数,至少为1
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+    /// 设置行号与列号的计算方式
+    pub fn with_span_options(mut self, options: SpanOptions) -> Self {
+        self.options = options;
+        self
+    }
+    /// 设置出错时的恢复方式
+    pub fn with_recovery(mut self, recovery: Recovery) -> Self {
+        self.recovery = recovery;
+        self
+    }
+    /// 设置关键字表,关键字表指定种别(默认为ID)的单词是关键字时改为关键字的种别
+    pub fn with_keywords(mut self, keywords: &'a KeywordTable<C>) -> Self {
+        self.keywords = Some(keywords);
+        self
+    }
+    /// 设置字面量的解码函数,解码失败时产生InvalidLiteral错误,其span指向词素中出错的部分,
+    /// 之后继续切分
+    pub fn with_decoders(mut self, decoders: &'a Decoders<C>) -> Self {
+        self.decoders = Some(decoders);
+        self
+    }
+    /// 设置附属成分规则。产生一个普通单词前要读到其后的换行或下一个普通单词,
+    /// 输入结束时剩下的附属成分挂在最后一个普通单词的trailing上,没有普通单词时照常产生
+    pub fn with_trivia(mut self, trivia: &'a Trivia<C>) -> Self {
+        self.trivia = Some(trivia);
+        self
+    }
+    /// 切分全部输入,返回所有单词与所有错误
+    pub fn collect_all(self) -> (Vec<Token<C>>, Vec<LexError>) {
+        let mut tokens = Vec::new();
+        let mut errors = Vec::new();
+        for result in self {
+            match result {
+                Ok(token) => tokens.push(token),
+                Err(error) => errors.push(error),
+            }
+        }
+        (tokens, errors)
+    }
+
+    /// 当前模式的序号
+    pub fn mode(&self) -> usize {
+        *self.modes.last().unwrap()
+    }
+    /// 进入模式mode,之后可以用pop_mode回到当前模式;没有该模式时panic
+    pub fn push_mode(&mut self, mode: &str) {
+        let index = self.mode_index(mode);
+        self.modes.push(index);
+    }
+    /// 回到进入当前模式之前的模式,已在栈底时不改变模式
+    pub fn pop_mode(&mut self) {
+        if self.modes.len() > 1 {
+            self.modes.pop();
+        }
+    }
+    /// 把当前模式替换为模式mode,没有该模式时panic
+    pub fn switch_mode(&mut self, mode: &str) {
+        let index = self.mode_index(mode);
+        *self.modes.last_mut().unwrap() = index;
+    }
+
+    /// 模式名对应的序号,由单个DFA构造或没有该模式时panic;
+    /// 规则的操作中的模式已由to_lexer检查,只有直接调用push_mode与switch_mode时可能panic
+    fn mode_index(&self, mode: &str) -> usize {
+        match self.lexer {
+            Some(lexer) => match lexer.mode_index(mode) {
+                Some(index) => index,
+                None => panic!("unknown lexer mode {:?}", mode),
+            },
+            None => panic!("tokenizer built from a single DFA has no lexer modes"),
+        }
+    }
+    /// 从输入中再读取一块追加到缓冲区,输入读完时设置eof
+    fn fill(&mut self) -> io::Result<()> {
+        let len = self.buffer.len();
+        self.buffer.resize(len + self.chunk_size, 0);
+        let result = loop {
+            match self.reader.read(&mut self.buffer[len..]) {
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                result => break result,
+            }
+        };
+        let read = *result.as_ref().unwrap_or(&0);
+        self.buffer.truncate(len + read);
+        self.eof |= read == 0;
+        result.map(|_| ())
+    }
+    /// 解码缓冲区中从pos开始的字符,字节不足时继续读取;
+    /// 已到输入末尾时返回None
+    fn decode_at(&mut self, pos: usize) -> Result<Option<Decoded>, LexError> {
+        loop {
+            match decode_char(&self.buffer[pos..]) {
+                Decoded::Incomplete if !self.eof => {
+                    self.fill().map_err(|error| LexError {
+                        span: Span::empty(self.position),
+                        kind: LexErrorKind::Io(error),
+                    })?;
+                }
+                Decoded::Incomplete if pos == self.buffer.len() => return Ok(None),
+                Decoded::Incomplete => return Ok(Some(Decoded::Invalid)),
+                decoded => return Ok(Some(decoded)),
+            }
+        }
+    }
+    /// 从缓冲区开头按最长匹配切分一个单词,返回(字节长度, DFA状态);
+    /// 没有规则接受任何非空前缀时返回None
+    fn longest_match(&mut self) -> Result<Option<(usize, i32)>, LexError> {
+        let mode = self.mode();
+        let dfa = &self.dfas[mode].dfa;
+        let (mut state, mut pos) = (0, 0);
+        let mut last = None;
+        while let Some(Decoded::Char(c, len)) = self.decode_at(pos)? {
+            state = match self.transitions[mode].next(state, c) {
+                Some(next) => next,
+                None => break,
+            };
+            pos += len;
+            if dfa.p_state_table[state as usize].state_type == StateType::MATCH {
+                last = Some((pos, state));
+            }
+        }
+        Ok(last)
+    }
+    /// 从pos开始跳过不满足stop的字符与不合法的字节,返回第一个满足stop的字符(或输入末尾)的位置
+    fn skip_until(&mut self, pos: usize, stop: impl Fn(char) -> bool) -> Result<usize, LexError> {
+        let mut pos = pos;
+        loop {
+            match self.decode_at(pos)? {
+                Some(Decoded::Char(c, _)) if stop(c) => return Ok(pos),
+                Some(Decoded::Char(_, len)) => pos += len,
+                Some(_) => pos += 1,
+                None => return Ok(pos),
+            }
+        }
+    }
+    /// 缓冲区中至少有len个字节,或已到输入末尾
+    fn ensure(&mut self, len: usize) -> Result<(), LexError> {
+        while self.buffer.len() < len && !self.eof {
+            self.fill().map_err(|error| LexError {
+                span: Span::empty(self.position),
+                kind: LexErrorKind::Io(error),
+            })?;
+        }
+        Ok(())
+    }
+    /// 从缓冲区开头移除len个字节,返回覆盖它们的错误
+    fn consume_error(&mut self, len: usize, kind: LexErrorKind) -> LexError {
+        // 不合法的字节按替换字符计算列号
+        let skipped: Vec<u8> = self.buffer.drain(..len).collect();
+        let mut end = self
+            .options
+            .advance(self.position, &String::from_utf8_lossy(&skipped));
+        end.offset = self.position.offset + len;
+        let span = Span {
+            start: self.position,
+            end,
+        };
+        self.position = end;
+        LexError { span, kind }
+    }
+    /// 从pos开始对open与close计数,深度从1开始,返回深度回到0时单词的字节长度;
+    /// 到输入末尾仍未闭合时消耗剩下的全部输入,返回从单词开头开始的Unterminated错误
+    fn scan_nested(&mut self, pos: usize, open: &str, close: &str) -> Result<usize, LexError> {
+        let (mut pos, mut depth) = (pos, 1);
+        while depth > 0 {
+            self.ensure(pos + open.len().max(close.len()))?;
+            let rest = &self.buffer[pos..];
+            if rest.starts_with(close.as_bytes()) {
+                depth -= 1;
+                pos += close.len();
+            } else if rest.starts_with(open.as_bytes()) {
+                depth += 1;
+                pos += open.len();
+            } else if rest.is_empty() {
+                let kind = LexErrorKind::Unterminated(close.to_string());
+                return Err(self.consume_error(pos, kind));
+            } else {
+                pos += 1;
+            }
+        }
+        if std::str::from_utf8(&self.buffer[..pos]).is_err() {
+            return Err(self.consume_error(pos, LexErrorKind::InvalidUtf8));
+        }
+        Ok(pos)
+    }
+    fn next_token(&mut self) -> Result<Option<Token<C>>, LexError> {
+        let (len, state) = match self.longest_match()? {
+            Some(matched) => matched,
+            None => {
+                let (kind, len) = match self.decode_at(0)? {
+                    None => return Ok(None),
+                    Some(Decoded::Char(c, len)) => (LexErrorKind::UnexpectedChar(c), len),
+                    Some(_) => (LexErrorKind::InvalidUtf8, 1),
+                };
+                let table = self.table;
+                let len = match self.recovery {
+                    Recovery::Stop | Recovery::SkipChar => len,
+                    Recovery::SkipToRuleStart => {
+                        let start = self.transitions[self.mode()].states[0].clone();
+                        self.skip_until(len, |c| Transitions::lookup(&start, c).is_some())?
+                    }
+                    Recovery::Synchronize(set) => {
+                        self.skip_until(len, |c| table.contains_char(set, c))?
+                    }
+                };
+                return Err(self.consume_error(len, kind));
+            }
+        };
+        let lexer_dfa = &self.dfas[self.mode()];
+        let rule = lexer_dfa.accepting[state as usize][0];
+        let category = lexer_dfa.dfa.p_state_table[state as usize].category;
+        let len = match self.lexer.and_then(|lexer| lexer.scanners[rule].as_ref()) {
+            Some(SubScanner::Nested { open, close }) => self.scan_nested(len, open, close)?,
+            None => len,
+        };
+        let text = String::from_utf8(self.buffer.drain(..len).collect()).unwrap();
+        let span = self.options.span(self.position, &text);
+        self.position = span.end;
+        let mut token = Token {
+            category,
+            rule,
+            text,
+            span,
+            value: None,
+            leading: Vec::new(),
+            trailing: Vec::new(),
+        };
+        if let Some(keywords) = self.keywords {
+            keywords.reclassify(&mut token);
+        }
+        let decoded = match self.decoders {
+            Some(decoders) => decoders.decode(&mut token),
+            None => Ok(()),
+        };
+        if let Some(lexer) = self.lexer {
+            match &lexer.actions[rule] {
+                ModeAction::Stay => {}
+                ModeAction::Push(mode) => self.push_mode(mode),
+                ModeAction::Pop => self.pop_mode(),
+                ModeAction::Switch(mode) => self.switch_mode(mode),
+            }
+        }
+        match decoded {
+            Ok(()) => Ok(Some(token)),
+            Err(error) => {
+                let start = self
+                    .options
+                    .advance(token.span.start, &token.text[..error.offset]);
+                let end = self
+                    .options
+                    .advance(start, &token.text[error.offset..error.offset + error.len]);
+                Err(LexError {
+                    span: Span { start, end },
+                    kind: LexErrorKind::InvalidLiteral(error.message),
+                })
+            }
+        }
+    }
+    /// 不考虑附属成分规则的下一个结果
+    fn next_raw(&mut self) -> Option<Result<Token<C>, LexError>> {
+        if self.done {
+            return None;
+        }
+        let result = self.next_token().transpose();
+        self.done = match &result {
+            Some(Ok(_)) => false,
+            // 解码失败的单词已经完整读过,不影响之后的切分
+            Some(Err(error)) => match error.kind {
+                LexErrorKind::Io(_) => true,
+                LexErrorKind::InvalidLiteral(_) => false,
+                _ => self.recovery == Recovery::Stop,
+            },
+            None => true,
+        };
+        result
+    }
+}
+
+impl<'a, R: Read, C: Category> Iterator for Tokenizer<'a, R, C> {
+    type Item = Result<Token<C>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let trivia = match self.trivia {
+            Some(trivia) => trivia,
+            None => return self.next_raw(),
+        };
+        loop {
+            let result = match self.pending.take() {
+                Some(error) => Some(Err(error)),
+                None => self.next_raw(),
+            };
+            let mut token = match result {
+                Some(Ok(token)) => token,
+                Some(Err(error)) => {
+                    // 错误在held之后产生,先产生held
+                    return match self.held.take() {
+                        Some(held) => {
+                            self.pending = Some(error);
+                            Some(Ok(held))
+                        }
+                        None => Some(Err(error)),
+                    };
+                }
+                None => {
+                    return match self.held.take() {
+                        Some(mut held) => {
+                            held.trailing.append(&mut self.leading);
+                            Some(Ok(held))
+                        }
+                        None if !self.leading.is_empty() => Some(Ok(self.leading.remove(0))),
+                        None => None,
+                    };
+                }
+            };
+            match trivia.mode(token.category) {
+                TriviaMode::Drop => {}
+                TriviaMode::Attach => match &mut self.held {
+                    Some(held) => match token.text.find('\n') {
+                        None => held.trailing.push(token),
+                        Some(index) => {
+                            // 空白在第一个换行处分开,换行之后的部分是下一个普通单词的leading
+                            let end = index + 1;
+                            let rest = if end < token.text.len()
+                                && token.text.chars().all(char::is_whitespace)
+                            {
+                                Some(split_token(&self.options, &mut token, end))
+                            } else {
+                                None
+                            };
+                            held.trailing.push(token);
+                            self.leading.extend(rest);
+                            return self.held.take().map(Ok);
+                        }
+                    },
+                    None => self.leading.push(token),
+                },
+                TriviaMode::Emit => {
+                    token.leading = std::mem::take(&mut self.leading);
+                    if let Some(held) = self.held.replace(token) {
+                        return Some(Ok(held));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 把单词在字节偏移at处分为两个,token保留前一部分,返回后一部分
+fn split_token<C: Category>(options: &SpanOptions, token: &mut Token<C>, at: usize) -> Token<C> {
+    let text = token.text.split_off(at);
+    token.span = options.span(token.span.start, &token.text);
+    Token {
+        category: token.category,
+        rule: token.rule,
+        span: options.span(token.span.end, &text),
+        text,
+        value: None,
+        leading: Vec::new(),
+        trailing: Vec::new(),
+    }
+}
+
+impl<C: Category> LexerDfa<C> {
+    /// 对reader中的输入做流式词法分析
+    pub fn tokenize<'a, R: Read>(
+        &'a self,
+        reader: R,
+        table: &'a CharSetTable,
+    ) -> Tokenizer<'a, R, C> {
+        Tokenizer::new(self, table, reader)
+    }
+}
+
+impl<C: Category> Lexer<C> {
+    /// 对reader中的输入做流式词法分析,从INITIAL模式开始
+    pub fn tokenize<'a, R: Read>(
+        &'a self,
+        reader: R,
+        table: &'a CharSetTable,
+    ) -> Tokenizer<'a, R, C> {
+        Tokenizer::from_lexer(self, table, reader)
+    }
+}
+
+/// 把一串分块的字节(如网络或管道中陆续到达的数据)当作一个`Read`
+pub struct ChunkReader<I: Iterator> {
+    chunks: I,
+    current: Option<I::Item>,
+    /// 当前块中已读取的字节数
+    position: usize,
+}
+
+impl<I: Iterator> ChunkReader<I> {
+    pub fn new(chunks: I) -> ChunkReader<I> {
+        ChunkReader {
+            chunks,
+            current: None,
+            position: 0,
+        }
+    }
+}
+
+impl<I> Read for ChunkReader<I>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            if let Some(chunk) = &self.current {
+                let rest = &chunk.as_ref()[self.position..];
+                if !rest.is_empty() {
+                    let len = rest.len().min(buf.len());
+                    buf[..len].copy_from_slice(&rest[..len]);
+                    self.position += len;
+                    return Ok(len);
+                }
+            }
+            self.current = self.chunks.next();
+            self.position = 0;
+            if self.current.is_none() {
+                return Ok(0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::{ModeAction, RuleSet, SubScanner};
+    use crate::span::ColumnUnit;
+
+    fn lexer(table: &mut CharSetTable) -> LexerDfa {
+        let mut rules = RuleSet::new();
+        for (category, pattern) in [
+            (LexemeCategory::ID, "[a-z变量]+"),
+            (LexemeCategory::FLOAT_CONST, "[0-9]+\\.[0-9]+"),
+            (LexemeCategory::INTEGER_CONST, "[0-9]+"),
+            (LexemeCategory::OPERATOR, "[=+.]"),
+            (LexemeCategory::SPACE_CONST, "[ \t\n]+"),
+        ] {
+            rules.add(category, pattern, table).unwrap();
+        }
+        rules.to_dfa(table)
+    }
+
+    fn texts(tokens: &[Token]) -> Vec<&str> {
+        tokens.iter().map(|token| token.text.as_str()).collect()
+    }
+
+    /// 转换表的二分查找与逐条边查找的结果一致
+    #[test]
+    fn test_transitions() {
+        let mut table = CharSetTable::new();
+        let lexer_dfa = lexer(&mut table);
+        let transitions = Transitions::new(&lexer_dfa.dfa, &table);
+        for state in 0..lexer_dfa.dfa.p_state_table.len() as i32 {
+            for c in "az变量量0.9=+ \t\n\u{0}-~中\u{10FFFF}".chars() {
+                assert_eq!(
+                    transitions.next(state, c),
+                    lexer_dfa.dfa.dfa_next(state, c, &table),
+                    "{} {:?}",
+                    state,
+                    c
+                );
+            }
+        }
+    }
+
+    /// 最长匹配与回退
+    #[test]
+    fn test_tokenize() {
+        let mut table = CharSetTable::new();
+        let lexer = lexer(&mut table);
+        let tokens: Vec<Token> = lexer
+            .tokenize("x1 = 3.14+1..2".as_bytes(), &table)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            texts(&tokens),
+            vec!["x", "1", " ", "=", " ", "3.14", "+", "1", ".", ".", "2"]
+        );
+        assert_eq!(tokens[5].category, LexemeCategory::FLOAT_CONST);
+        assert_eq!(tokens[5].rule, 1);
+        assert_eq!(tokens[5].span.start.offset, 5);
+        assert_eq!(tokens[7].category, LexemeCategory::INTEGER_CONST);
+        assert_eq!(lexer.tokenize(io::empty(), &table).count(), 0);
+    }
+
+    /// 跨越分块边界的单词,分块可以切断一个字符的UTF-8编码
+    #[test]
+    fn test_chunk_boundaries() {
+        let mut table = CharSetTable::new();
+        let lexer = lexer(&mut table);
+        let input = "变量 = 12.5\nabc+变量x";
+        let expected: Vec<Token> = lexer
+            .tokenize(input.as_bytes(), &table)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            texts(&expected),
+            vec!["变量", " ", "=", " ", "12.5", "\n", "abc", "+", "变量x"]
+        );
+        for size in 1..8 {
+            let chunks = ChunkReader::new(input.as_bytes().chunks(size));
+            let tokens: Vec<Token> = Tokenizer::new(&lexer, &table, chunks)
+                .with_chunk_size(size)
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(tokens, expected, "{}", size);
+        }
+    }
+
+    /// 出错后迭代结束
+    #[test]
+    fn test_errors() {
+        let mut table = CharSetTable::new();
+        let lexer = lexer(&mut table);
+        let mut tokens = lexer.tokenize("ab ?c".as_bytes(), &table);
+        assert_eq!(tokens.next().unwrap().unwrap().text, "ab");
+        assert_eq!(tokens.next().unwrap().unwrap().text, " ");
+        let error = tokens.next().unwrap().unwrap_err();
+        assert!(matches!(error.kind, LexErrorKind::UnexpectedChar('?')));
+        assert_eq!(error.span.start.offset, 3);
+        assert!(tokens.next().is_none());
+        // 单词中途遇到不合法的UTF-8时在其之前结束
+        let bytes = [b'a', b'b', 0xff, b'c'];
+        let results: Vec<_> = lexer.tokenize(&bytes[..], &table).collect();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap().text, "ab");
+        let error = results[1].as_ref().unwrap_err();
+        assert!(matches!(error.kind, LexErrorKind::InvalidUtf8));
+        assert_eq!(error.to_string(), "1:3: invalid UTF-8");
+        // 输入在一个字符的UTF-8编码中途结束
+        let bytes = &"a变".as_bytes()[..3];
+        let results: Vec<_> = lexer.tokenize(bytes, &table).collect();
+        assert_eq!(results[0].as_ref().unwrap().text, "a");
+        assert_eq!(results[1].as_ref().unwrap_err().span.start.offset, 1);
+    }
+
+    /// 单词与错误的行号、列号
+    #[test]
+    fn test_spans() {
+        let mut table = CharSetTable::new();
+        let lexer = lexer(&mut table);
+        let options = SpanOptions {
+            tab_width: 4,
+            column_unit: ColumnUnit::Utf16,
+        };
+        let input = "ab\n\t变量 = 1\n?";
+        let results: Vec<_> = lexer
+            .tokenize(input.as_bytes(), &table)
+            .with_span_options(options)
+            .collect();
+        let spans: Vec<String> = results
+            .iter()
+            .filter_map(|result| result.as_ref().ok())
+            .map(|token| token.span.to_string())
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                "1:1-1:3",
+                "1:3-2:5",
+                "2:5-2:7",
+                "2:7-2:8",
+                "2:8-2:9",
+                "2:9-2:10",
+                "2:10-2:11",
+                "2:11-3:1"
+            ]
+        );
+        let error = results.last().unwrap().as_ref().unwrap_err();
+        assert_eq!(error.span.to_string(), "3:1-3:2");
+        assert_eq!(error.span.start.offset, input.len() - 1);
+        assert_eq!(error.to_string(), "3:1: unexpected character '?'");
+    }
+
+    /// 各种恢复方式跳过的输入与收集的错误
+    #[test]
+    fn test_recovery() {
+        let mut table = CharSetTable::new();
+        let lexer = lexer(&mut table);
+        let sync = table.from_ranges(&[(';', ';'), ('\n', '\n')]);
+        let input = "a ?#b;c\n@@\n1";
+        let skipped = |recovery: Recovery| {
+            let (tokens, errors) = lexer
+                .tokenize(input.as_bytes(), &table)
+                .with_recovery(recovery)
+                .collect_all();
+            let errors: Vec<&str> = errors
+                .iter()
+                .map(|error| &input[error.span.start.offset..error.span.end.offset])
+                .collect();
+            (tokens.len(), errors)
+        };
+        assert_eq!(skipped(Recovery::Stop), (2, vec!["?"]));
+        assert_eq!(
+            skipped(Recovery::SkipChar),
+            (7, vec!["?", "#", ";", "@", "@"])
+        );
+        assert_eq!(
+            skipped(Recovery::SkipToRuleStart),
+            (7, vec!["?#", ";", "@@"])
+        );
+        assert_eq!(
+            skipped(Recovery::Synchronize(sync)),
+            (5, vec!["?#b", ";c", "@@"])
+        );
+        // 跳过不合法的UTF-8
+        let bytes = [b'a', 0xff, 0xfe, b' ', b'b'];
+        let (tokens, errors) = lexer
+            .tokenize(&bytes[..], &table)
+            .with_recovery(Recovery::SkipToRuleStart)
+            .collect_all();
+        assert_eq!(texts(&tokens), vec!["a", " ", "b"]);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0].kind, LexErrorKind::InvalidUtf8));
+        assert_eq!(errors[0].span.len(), 2);
+        assert_eq!(tokens[1].span.start.column, 4);
+    }
+
+    /// 字符串模式:规则的操作与运行时切换模式
+    #[test]
+    fn test_modes() {
+        let mut table = CharSetTable::new();
+        let mut rules = RuleSet::new();
+        rules.add(LexemeCategory::ID, "[a-z]+", &mut table).unwrap();
+        rules
+            .add(LexemeCategory::SPACE_CONST, " +", &mut table)
+            .unwrap();
+        let open = rules
+            .add(LexemeCategory::OPERATOR, "\"", &mut table)
+            .unwrap();
+        rules.set_action(open, ModeAction::Push("STRING".to_string()));
+        rules
+            .add_in(
+                &["STRING"],
+                LexemeCategory::STRING_CONST,
+                "[^\"\\\\]+|\\\\.",
+                &mut table,
+            )
+            .unwrap();
+        let close = rules
+            .add_in(&["STRING"], LexemeCategory::OPERATOR, "\"", &mut table)
+            .unwrap();
+        rules.set_action(close, ModeAction::Pop);
+        let lexer = rules.to_lexer(&mut table).unwrap();
+        let input = "say \"hi \\\" x\" ok";
+        let tokens: Vec<(LexemeCategory, String)> = lexer
+            .tokenize(input.as_bytes(), &table)
+            .map(|token| {
+                let token = token.unwrap();
+                (token.category, token.text)
+            })
+            .collect();
+        let expected = [
+            (LexemeCategory::ID, "say"),
+            (LexemeCategory::SPACE_CONST, " "),
+            (LexemeCategory::OPERATOR, "\""),
+            (LexemeCategory::STRING_CONST, "hi "),
+            (LexemeCategory::STRING_CONST, "\\\""),
+            (LexemeCategory::STRING_CONST, " x"),
+            (LexemeCategory::OPERATOR, "\""),
+            (LexemeCategory::SPACE_CONST, " "),
+            (LexemeCategory::ID, "ok"),
+        ];
+        assert_eq!(tokens.len(), expected.len());
+        for (token, (category, text)) in tokens.iter().zip(expected) {
+            assert_eq!((token.0, token.1.as_str()), (category, text));
+        }
+        // 运行时切换模式:STRING模式中空格不是单独的单词
+        let mut tokenizer = lexer.tokenize("a b".as_bytes(), &table);
+        assert_eq!(tokenizer.next().unwrap().unwrap().text, "a");
+        tokenizer.switch_mode("STRING");
+        assert_eq!(tokenizer.mode(), 1);
+        let token = tokenizer.next().unwrap().unwrap();
+        assert_eq!(
+            (token.category, token.text.as_str()),
+            (LexemeCategory::STRING_CONST, " b")
+        );
+        tokenizer.pop_mode();
+        assert_eq!(tokenizer.mode(), 1);
+    }
+
+    /// 用关键字表重新分类ID
+    #[test]
+    fn test_keywords() {
+        let mut table = CharSetTable::new();
+        let lexer = lexer(&mut table);
+        let mut keywords = KeywordTable::new();
+        keywords.insert("and", LexemeCategory::LOGIC_OPERATOR);
+        keywords.insert("or", LexemeCategory::LOGIC_OPERATOR);
+        let categories: Vec<LexemeCategory> = lexer
+            .tokenize("x and or1 ors".as_bytes(), &table)
+            .with_keywords(&keywords)
+            .map(|token| token.unwrap().category)
+            .filter(|&category| category != LexemeCategory::SPACE_CONST)
+            .collect();
+        assert_eq!(
+            categories,
+            vec![
+                LexemeCategory::ID,
+                LexemeCategory::LOGIC_OPERATOR,
+                LexemeCategory::LOGIC_OPERATOR,
+                LexemeCategory::INTEGER_CONST,
+                LexemeCategory::ID,
+            ]
+        );
+    }
+
+    /// 嵌套的块注释,未闭合时产生从注释开头开始的错误
+    #[test]
+    fn test_nested_comment() {
+        let mut table = CharSetTable::new();
+        let mut rules = RuleSet::new();
+        let comment = rules.add(LexemeCategory::NOTE, r"/\*", &mut table).unwrap();
+        rules
+            .set_scanner(
+                comment,
+                SubScanner::Nested {
+                    open: "/*".to_string(),
+                    close: "*/".to_string(),
+                },
+            )
+            .unwrap();
+        rules.add(LexemeCategory::ID, "[a-z]+", &mut table).unwrap();
+        rules
+            .add(LexemeCategory::OPERATOR, r"[/*]", &mut table)
+            .unwrap();
+        rules
+            .add(LexemeCategory::SPACE_CONST, "[ \n]+", &mut table)
+            .unwrap();
+        let lexer = rules.to_lexer(&mut table).unwrap();
+        let texts = |input: &str, chunk_size| -> Vec<String> {
+            lexer
+                .tokenize(input.as_bytes(), &table)
+                .with_chunk_size(chunk_size)
+                .map(|result| match result {
+                    Ok(token) => token.text,
+                    Err(error) => error.to_string(),
+                })
+                .filter(|text| text != " ")
+                .collect()
+        };
+        for chunk_size in [1, 3, 4096] {
+            assert_eq!(
+                texts("a /* x /* 中 */ z */ b*/", chunk_size),
+                vec!["a", "/* x /* 中 */ z */", "b", "*", "/"]
+            );
+            assert_eq!(texts("/**/*/", chunk_size), vec!["/**/", "*", "/"]);
+        }
+        let (tokens, errors) = lexer
+            .tokenize("a\n/* x /* y */".as_bytes(), &table)
+            .collect_all();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].span.to_string(), "2:1-2:13");
+        assert_eq!(
+            errors[0].to_string(),
+            "2:1: unterminated token, expected \"*/\""
+        );
+    }
+
+    /// 附属成分挂在相邻的单词上,能还原原文;也可以丢弃
+    #[test]
+    fn test_trivia() {
+        let mut table = CharSetTable::new();
+        let lexer = crate::rule_library::c_like_rules(&mut table).to_dfa(&mut table);
+        let input = "// head\nint x = 1; // one\n\n/* a */ y\n";
+        let preserve = Trivia::preserve();
+        let tokens: Vec<Token> = lexer
+            .tokenize(input.as_bytes(), &table)
+            .with_trivia(&preserve)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(texts(&tokens), vec!["int", "x", "=", "1", ";", "y"]);
+        assert_eq!(texts(&tokens[0].leading), vec!["// head", "\n"]);
+        assert_eq!(texts(&tokens[0].trailing), vec![" "]);
+        assert!(tokens[3].trailing.is_empty());
+        assert_eq!(texts(&tokens[4].trailing), vec![" ", "// one", "\n"]);
+        assert_eq!(texts(&tokens[5].leading), vec!["\n", "/* a */", " "]);
+        assert_eq!(tokens[5].leading[0].span.to_string(), "3:1-4:1");
+        assert_eq!(texts(&tokens[5].trailing), vec!["\n"]);
+        let text: String = tokens.iter().map(Token::full_text).collect();
+        assert_eq!(text, input);
+
+        let discard = Trivia::discard();
+        let (tokens, _) = lexer
+            .tokenize(input.as_bytes(), &table)
+            .with_trivia(&discard)
+            .collect_all();
+        assert_eq!(texts(&tokens), vec!["int", "x", "=", "1", ";", "y"]);
+        assert!(tokens.iter().all(|token| token.leading.is_empty()));
+
+        // 错误按输入中的顺序产生
+        let results: Vec<String> = lexer
+            .tokenize("a @ b".as_bytes(), &table)
+            .with_trivia(&preserve)
+            .with_recovery(Recovery::SkipChar)
+            .map(|result| match result {
+                Ok(token) => token.full_text(),
+                Err(error) => error.to_string(),
+            })
+            .collect();
+        assert_eq!(results, vec!["a ", "1:3: unexpected character '@'", " b"]);
+
+        // 没有普通单词时附属成分照常产生
+        let (tokens, _) = lexer
+            .tokenize("  // x".as_bytes(), &table)
+            .with_trivia(&preserve)
+            .collect_all();
+        assert_eq!(texts(&tokens), vec!["  ", "// x"]);
+    }
+
+    /// 字面量的值,解码失败的错误指向出错的部分且不结束切分
+    #[test]
+    fn test_decoders() {
+        let mut table = CharSetTable::new();
+        let lexer = crate::rule_library::c_like_rules(&mut table).to_dfa(&mut table);
+        let decoders = Decoders::standard();
+        let input = "x = 0x10u + 2.5e1;\ns = \"a\\tb\" 99999999999999999999 \"\\uD800\" 7";
+        let (tokens, errors) = lexer
+            .tokenize(input.as_bytes(), &table)
+            .with_decoders(&decoders)
+            .collect_all();
+        let values: Vec<&TokenValue> = tokens
+            .iter()
+            .filter_map(|token| token.value.as_ref())
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                &TokenValue::UInt(16),
+                &TokenValue::Float(25.0),
+                &TokenValue::Str("a\tb".to_string()),
+                &TokenValue::Int(7),
+            ]
+        );
+        assert_eq!(tokens[0].value, None);
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(errors[0].kind, LexErrorKind::InvalidLiteral(_)));
+        assert_eq!(errors[0].span.to_string(), "2:12-2:32");
+        assert_eq!(errors[1].span.to_string(), "2:34-2:40");
+        assert_eq!(
+            errors[1].to_string(),
+            "2:34: escape sequence is not a valid character"
+        );
+    }
+
+    /// 自定义的种别类型与在其上的关键字表
+    #[test]
+    fn test_custom_category() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        enum Kind {
+            Word,
+            Let,
+            Blank,
+            None,
+        }
+        impl Category for Kind {
+            fn empty() -> Self {
+                Kind::None
+            }
+        }
+        let mut table = CharSetTable::new();
+        let mut rules = RuleSet::new();
+        rules.add(Kind::Word, "[a-zA-Z]+", &mut table).unwrap();
+        rules.add(Kind::Blank, " ", &mut table).unwrap();
+        let lexer = rules.to_lexer(&mut table).unwrap();
+        let mut keywords = KeywordTable::for_category(Kind::Word, true);
+        keywords.insert("let", Kind::Let);
+        let categories: Vec<Kind> = lexer
+            .tokenize("LET x".as_bytes(), &table)
+            .with_keywords(&keywords)
+            .map(|token| token.unwrap().category)
+            .collect();
+        assert_eq!(categories, vec![Kind::Let, Kind::Blank, Kind::Word]);
+    }
+}
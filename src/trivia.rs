@@ -0,0 +1,189 @@
+use super::graph::{Category, LexemeCategory};
+
+/// 一个种别的单词的处理方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TriviaMode {
+    /// 作为普通单词产生
+    #[default]
+    Emit,
+    /// 丢弃
+    Drop,
+    /// 作为附属成分挂在相邻的普通单词上:同一行内紧跟在普通单词之后的(直到含换行的那个为止)
+    /// 作为其trailing,其余的作为下一个普通单词的leading;含换行的空白在第一个换行处分开,
+    /// 换行及之前的部分是trailing,之后的部分是leading
+    Attach,
+}
+
+/// **附属成分规则**:空白、注释等单词按种别丢弃、照常产生或挂在相邻的普通单词上,
+/// 编译器丢弃它们,格式化工具保留它们,两者可以使用同一个词法分析器
+#[derive(Clone, Debug)]
+pub struct Trivia<C = LexemeCategory> {
+    /// 种别 -> 处理方式,没有列出的种别为Emit
+    modes: Vec<(C, TriviaMode)>,
+}
+
+impl<C> Default for Trivia<C> {
+    fn default() -> Self {
+        Trivia { modes: Vec::new() }
+    }
+}
+
+impl Trivia {
+    /// 丢弃SPACE_CONST与NOTE
+    pub fn discard() -> Trivia {
+        let mut trivia = Trivia::new();
+        trivia.set(LexemeCategory::SPACE_CONST, TriviaMode::Drop);
+        trivia.set(LexemeCategory::NOTE, TriviaMode::Drop);
+        trivia
+    }
+    /// 把SPACE_CONST与NOTE挂在相邻的普通单词上
+    pub fn preserve() -> Trivia {
+        let mut trivia = Trivia::new();
+        trivia.set(LexemeCategory::SPACE_CONST, TriviaMode::Attach);
+        trivia.set(LexemeCategory::NOTE, TriviaMode::Attach);
+        trivia
+    }
+}
+
+impl<C: Category> Trivia<C> {
+    pub fn new() -> Trivia<C> {
+        Trivia::default()
+    }
+    /// 设置种别category的处理方式,已有时替换
+    pub fn set(&mut self, category: C, mode: TriviaMode) {
+        match self.modes.iter_mut().find(|(item, _)| *item == category) {
+            Some((_, item)) => *item = mode,
+            None => self.modes.push((category, mode)),
+        }
+    }
+    /// 种别category的处理方式
+    pub fn mode(&self, category: C) -> TriviaMode {
+        self.modes
+            .iter()
+            .find(|(item, _)| *item == category)
+            .map_or(TriviaMode::Emit, |&(_, mode)| mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::char_set::CharSetTable;
+    use crate::rule_library::c_like_rules;
+    use crate::tokenizer::{Recovery, Token};
+
+    /// 用C风格的规则切分input,单词写成`leading|text|trailing`,错误写成其描述
+    fn tokenize(input: &str, trivia: &Trivia, recovery: Recovery) -> Vec<String> {
+        let mut table = CharSetTable::new();
+        let lexer = c_like_rules(&mut table).to_dfa(&mut table);
+        let texts = |tokens: &[Token]| -> Vec<String> {
+            tokens.iter().map(|token| token.text.clone()).collect()
+        };
+        lexer
+            .tokenize(input.as_bytes(), &table)
+            .with_trivia(trivia)
+            .with_recovery(recovery)
+            .map(|result| match result {
+                Ok(token) => format!(
+                    "{:?}|{}|{:?}",
+                    texts(&token.leading),
+                    token.text,
+                    texts(&token.trailing)
+                ),
+                Err(error) => error.to_string(),
+            })
+            .collect()
+    }
+
+    /// 没有设置的种别照常产生,设置可以替换
+    #[test]
+    fn test_mode() {
+        let mut trivia = Trivia::preserve();
+        assert_eq!(trivia.mode(LexemeCategory::NOTE), TriviaMode::Attach);
+        assert_eq!(trivia.mode(LexemeCategory::ID), TriviaMode::Emit);
+        trivia.set(LexemeCategory::NOTE, TriviaMode::Drop);
+        assert_eq!(trivia.mode(LexemeCategory::NOTE), TriviaMode::Drop);
+        assert_eq!(
+            Trivia::discard().mode(LexemeCategory::SPACE_CONST),
+            TriviaMode::Drop
+        );
+    }
+
+    /// 每个种别分别丢弃、挂在相邻的单词上或照常产生;含换行的空白在第一个换行处分开
+    #[test]
+    fn test_drop_and_attach() {
+        let input = "a /* c */\n  b // d\n";
+        let mut trivia = Trivia::new();
+        trivia.set(LexemeCategory::SPACE_CONST, TriviaMode::Attach);
+        trivia.set(LexemeCategory::NOTE, TriviaMode::Drop);
+        assert_eq!(
+            tokenize(input, &trivia, Recovery::Stop),
+            vec![r#"[]|a|[" ", "\n"]"#, r#"["  "]|b|[" ", "\n"]"#,]
+        );
+        trivia.set(LexemeCategory::SPACE_CONST, TriviaMode::Drop);
+        trivia.set(LexemeCategory::NOTE, TriviaMode::Attach);
+        assert_eq!(
+            tokenize(input, &trivia, Recovery::Stop),
+            vec![r#"[]|a|["/* c */"]"#, r#"[]|b|["// d"]"#]
+        );
+        // 空白照常产生时,注释挂在空白上
+        trivia.set(LexemeCategory::SPACE_CONST, TriviaMode::Emit);
+        assert_eq!(
+            tokenize(input, &trivia, Recovery::Stop),
+            vec![
+                "[]|a|[]",
+                r#"[]| |["/* c */"]"#,
+                "[]|\n  |[]",
+                "[]|b|[]",
+                r#"[]| |["// d"]"#,
+                "[]|\n|[]",
+            ]
+        );
+        // 同一行的附属成分都是trailing,换行之后的空行与缩进是leading
+        assert_eq!(
+            tokenize("x \t\n\n  y", &Trivia::preserve(), Recovery::Stop),
+            vec![r#"[]|x|[" \t\n"]"#, r#"["\n  "]|y|[]"#]
+        );
+    }
+
+    /// 输入结束时剩下的附属成分挂在最后一个单词上,没有普通单词时照常产生
+    #[test]
+    fn test_trivia_at_eof() {
+        let preserve = Trivia::preserve();
+        assert_eq!(
+            tokenize("x  /* end */", &preserve, Recovery::Stop),
+            vec![r#"[]|x|["  ", "/* end */"]"#]
+        );
+        assert_eq!(
+            tokenize("  /* only */\n", &preserve, Recovery::Stop),
+            vec!["[]|  |[]", "[]|/* only */|[]", "[]|\n|[]"]
+        );
+        assert_eq!(
+            tokenize("  // x", &Trivia::discard(), Recovery::Stop),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            tokenize("", &preserve, Recovery::Stop),
+            Vec::<String>::new()
+        );
+    }
+
+    /// 错误在挂着附属成分的单词之后产生时,先产生该单词,再产生错误
+    #[test]
+    fn test_errors_after_held_token() {
+        let preserve = Trivia::preserve();
+        assert_eq!(
+            tokenize("a @ b\n@", &preserve, Recovery::SkipChar),
+            vec![
+                r#"[]|a|[" "]"#,
+                "1:3: unexpected character '@'",
+                r#"[" "]|b|["\n"]"#,
+                "2:1: unexpected character '@'",
+            ]
+        );
+        assert_eq!(
+            tokenize("a @ b", &preserve, Recovery::Stop),
+            vec![r#"[]|a|[" "]"#, "1:3: unexpected character '@'"]
+        );
+    }
+}
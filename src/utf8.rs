@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+
+use super::char_set::CharSetTable;
+use super::graph::{Category, DriverType, Edge, Graph, State, StateType};
+
+/// 各编码长度能表示的最大码位
+const MAX_CODE_BY_LEN: [u32; 4] = [0x7F, 0x7FF, 0xFFFF, 0x10FFFF];
+
+/// 把字符范围[from_char, to_char]转换为UTF-8字节范围序列的集合:
+/// 每个序列的第i项是第i个字节的取值范围,所有序列匹配的字节串恰好是范围中字符的UTF-8编码
+pub fn utf8_sequences(from_char: char, to_char: char) -> Vec<Vec<(u8, u8)>> {
+    let mut result = Vec::new();
+    let mut stack = vec![(from_char as u32, to_char as u32)];
+    while let Some((from, to)) = stack.pop() {
+        if from > to {
+            continue;
+        }
+        // 跳过代理区
+        if from < 0xE000 && to > 0xD7FF {
+            stack.push((0xE000, to));
+            stack.push((from, 0xD7FF));
+            continue;
+        }
+        // 按编码长度拆分
+        if let Some(&max) = MAX_CODE_BY_LEN.iter().find(|&&max| from <= max && max < to) {
+            stack.push((max + 1, to));
+            stack.push((from, max));
+            continue;
+        }
+        // 按后续字节拆分,使得每一位字节的取值都是一个完整的区间
+        let len = encode(from).len();
+        let mut split = false;
+        for i in 1..len {
+            let mask = (1u32 << (6 * i)) - 1;
+            if from & !mask != to & !mask {
+                if from & mask != 0 {
+                    stack.push(((from | mask) + 1, to));
+                    stack.push((from, from | mask));
+                    split = true;
+                    break;
+                }
+                if to & mask != mask {
+                    stack.push((to & !mask, to));
+                    stack.push((from, (to & !mask) - 1));
+                    split = true;
+                    break;
+                }
+            }
+        }
+        if !split {
+            let (from_bytes, to_bytes) = (encode(from), encode(to));
+            result.push(from_bytes.into_iter().zip(to_bytes).collect());
+        }
+    }
+    result
+}
+
+/// 码位的UTF-8编码
+fn encode(code: u32) -> Vec<u8> {
+    let c = char::from_u32(code).expect("surrogates are split off before encoding");
+    let mut buf = [0u8; 4];
+    c.encode_utf8(&mut buf).as_bytes().to_vec()
+}
+
+impl<C: Category> Graph<C> {
+    /// 把字符上的自动机编译为等价的UTF-8字节上的自动机:每条CHAR边和CHARSET边换成
+    /// 其字符范围的UTF-8字节范围序列(共享前缀)。字节自动机中CHAR边的driver_id为字节值,
+    /// CHARSET边的字符集加入到table中,以U+0000..U+00FF表示字节0x00..0xFF。
+    /// 非法的UTF-8字节串(过长编码、代理区、截断的序列、0xF5..0xFF等)不匹配任何边
+    pub fn to_utf8_graph(&self, table: &mut CharSetTable) -> Graph<C> {
+        let mut graph = Graph {
+            graph_id: self.graph_id,
+            num_of_states: 0,
+            p_edge_table: Vec::new(),
+            p_state_table: self.p_state_table.clone(),
+        };
+        let mut next_id = self
+            .p_state_table
+            .iter()
+            .map(|state| state.state_id)
+            .max()
+            .unwrap_or(-1)
+            + 1;
+        let old_end = next_id - 1;
+        // 字节范围 -> 字符集id,避免重复生成相同的字符集
+        let mut byte_sets: HashMap<(u8, u8), i32> = HashMap::new();
+        for edge in self.p_edge_table.iter() {
+            let ranges = match edge.driver_type {
+                DriverType::NULL => {
+                    graph.p_edge_table.push(edge.clone());
+                    continue;
+                }
+                DriverType::CHAR => match char::from_u32(edge.driver_id as u32) {
+                    Some(c) => vec![(c, c)],
+                    None => continue,
+                },
+                DriverType::CHARSET => table.ranges(edge.driver_id),
+            };
+            // (状态, 字节范围) -> 下一个中间状态,使同一条边展开的序列共享前缀
+            let mut trie: HashMap<(i32, (u8, u8)), i32> = HashMap::new();
+            for &(from_char, to_char) in ranges.iter() {
+                for sequence in utf8_sequences(from_char, to_char) {
+                    let mut state = edge.from_state;
+                    for (index, &range) in sequence.iter().enumerate() {
+                        let (driver_type, driver_id) = byte_driver(range, table, &mut byte_sets);
+                        if index == sequence.len() - 1 {
+                            graph.p_edge_table.push(Edge {
+                                from_state: state,
+                                next_state: edge.next_state,
+                                driver_id,
+                                driver_type,
+                            });
+                            break;
+                        }
+                        state = match trie.get(&(state, range)) {
+                            Some(&next) => next,
+                            None => {
+                                let next = next_id;
+                                next_id += 1;
+                                trie.insert((state, range), next);
+                                graph.p_state_table.push(State {
+                                    state_id: next,
+                                    state_type: StateType::UNMATCH,
+                                    category: C::empty(),
+                                });
+                                graph.p_edge_table.push(Edge {
+                                    from_state: state,
+                                    next_state: next,
+                                    driver_id,
+                                    driver_type,
+                                });
+                                next
+                            }
+                        };
+                    }
+                }
+            }
+        }
+        // 交换原结束状态与最后一个中间状态的序号,保持结束状态在状态表的最后
+        let new_end = next_id - 1;
+        if new_end != old_end {
+            let swap_id = |id: i32| match id {
+                id if id == old_end => new_end,
+                id if id == new_end => old_end,
+                id => id,
+            };
+            for state in graph.p_state_table.iter_mut() {
+                state.state_id = swap_id(state.state_id);
+            }
+            for edge in graph.p_edge_table.iter_mut() {
+                edge.from_state = swap_id(edge.from_state);
+                edge.next_state = swap_id(edge.next_state);
+            }
+            graph.p_state_table.sort_by_key(|state| state.state_id);
+        }
+        graph.num_of_states = graph.p_state_table.len() as i32;
+        graph
+    }
+    /// 在字节串上模拟由to_utf8_graph得到的字节自动机,判断整个输入能否被接受
+    pub fn simulate_bytes(&self, input: &[u8], table: &CharSetTable) -> bool {
+        let mut state_vec = self.epsilon_closure(&[0]);
+        for &byte in input.iter() {
+            if state_vec.is_empty() {
+                return false;
+            }
+            state_vec = self.epsilon_closure(&self.move_by_char(&state_vec, byte as char, table));
+        }
+        self.is_match_states(&state_vec)
+    }
+}
+
+/// 字节范围对应的驱动:单个字节为CHAR边,否则为CHARSET边
+fn byte_driver(
+    range: (u8, u8),
+    table: &mut CharSetTable,
+    byte_sets: &mut HashMap<(u8, u8), i32>,
+) -> (DriverType, i32) {
+    if range.0 == range.1 {
+        return (DriverType::CHAR, range.0 as i32);
+    }
+    let index_id = *byte_sets
+        .entry(range)
+        .or_insert_with(|| table.from_ranges(&[(range.0 as char, range.1 as char)]));
+    (DriverType::CHARSET, index_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::compile;
+
+    /// 全体字符的UTF-8字节范围序列
+    #[test]
+    fn test_utf8_sequences() {
+        let sequences = utf8_sequences('\0', char::MAX);
+        let expected: Vec<Vec<(u8, u8)>> = vec![
+            vec![(0x00, 0x7F)],
+            vec![(0xC2, 0xDF), (0x80, 0xBF)],
+            vec![(0xE0, 0xE0), (0xA0, 0xBF), (0x80, 0xBF)],
+            vec![(0xE1, 0xEC), (0x80, 0xBF), (0x80, 0xBF)],
+            vec![(0xED, 0xED), (0x80, 0x9F), (0x80, 0xBF)],
+            vec![(0xEE, 0xEF), (0x80, 0xBF), (0x80, 0xBF)],
+            vec![(0xF0, 0xF0), (0x90, 0xBF), (0x80, 0xBF), (0x80, 0xBF)],
+            vec![(0xF1, 0xF3), (0x80, 0xBF), (0x80, 0xBF), (0x80, 0xBF)],
+            vec![(0xF4, 0xF4), (0x80, 0x8F), (0x80, 0xBF), (0x80, 0xBF)],
+        ];
+        assert_eq!(sequences, expected);
+        // 每个字符的编码恰好被一个序列匹配
+        for code in (0..=0x10FFFF)
+            .step_by(97)
+            .chain([0x7FF, 0x800, 0xFFFF, 0x10000])
+        {
+            let c = match char::from_u32(code) {
+                Some(c) => c,
+                None => continue,
+            };
+            let mut buf = [0u8; 4];
+            let bytes = c.encode_utf8(&mut buf).as_bytes();
+            let matched = utf8_sequences('\u{80}', '\u{10FFFF}')
+                .iter()
+                .filter(|sequence| {
+                    sequence.len() == bytes.len()
+                        && sequence
+                            .iter()
+                            .zip(bytes)
+                            .all(|(&(from, to), &byte)| from <= byte && byte <= to)
+                })
+                .count();
+            assert_eq!(matched, if code < 0x80 { 0 } else { 1 }, "{:X}", code);
+        }
+    }
+
+    /// 字节自动机与字符自动机接受相同的串
+    #[test]
+    fn test_utf8_graph() {
+        let mut table = CharSetTable::new();
+        let graph = compile("[α-ω]+|\\w+中.", &mut table).unwrap();
+        let byte_graph = graph.to_utf8_graph(&mut table);
+        let end = byte_graph.p_state_table.last().unwrap();
+        assert_eq!(end.state_type, StateType::MATCH);
+        for input in [
+            "αβγ",
+            "abc中x",
+            "x_1中😀",
+            "αb",
+            "中",
+            "",
+            "abc中",
+            "ab中\n",
+        ] {
+            assert_eq!(
+                byte_graph.simulate_bytes(input.as_bytes(), &table),
+                graph.simulate(input, &table),
+                "{}",
+                input
+            );
+        }
+    }
+
+    /// 非法的UTF-8字节串不被接受
+    #[test]
+    fn test_invalid_utf8() {
+        let mut table = CharSetTable::new();
+        let graph = compile(".*", &mut table).unwrap().to_utf8_graph(&mut table);
+        assert!(graph.simulate_bytes("ok中😀".as_bytes(), &table));
+        let invalid: [&[u8]; 6] = [
+            b"\xFF",
+            b"\xC0\x80",
+            b"\xED\xA0\x80",
+            b"\xE4\xB8",
+            b"\xF4\x90\x80\x80",
+            b"a\x80",
+        ];
+        for input in invalid {
+            assert!(!graph.simulate_bytes(input, &table), "{:?}", input);
+        }
+    }
+}